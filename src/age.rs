@@ -1,6 +1,8 @@
 use bevy::prelude::*;
 
-use crate::{life::Alive, state::RunState};
+use crate::{calendar::YEAR_LENGTH, life::Alive, state::RunState};
+
+const DAYS_PER_YEAR: i32 = YEAR_LENGTH.cast_signed();
 
 #[derive(Component, Debug)]
 pub struct Age {
@@ -11,12 +13,20 @@ impl Age {
     pub fn new(days: i32) -> Self {
         Age { days }
     }
+
+    pub fn days(&self) -> i32 {
+        self.days
+    }
+
+    pub fn years(&self) -> i32 {
+        self.days / DAYS_PER_YEAR
+    }
 }
 
 impl std::fmt::Display for Age {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let years = self.days / 365;
-        let days = self.days % 365;
+        let years = self.days / DAYS_PER_YEAR;
+        let days = self.days % DAYS_PER_YEAR;
         write!(f, "{years} Years, {days} Days",)
     }
 }
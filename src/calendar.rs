@@ -5,7 +5,7 @@ use bevy::utils::HashMap;
 
 use crate::state::RunState;
 
-const YEAR_LENGTH: u32 = 365;
+pub(crate) const YEAR_LENGTH: u32 = 365;
 
 #[derive(Debug, Eq, Hash, PartialEq, Clone, Copy)]
 pub enum MonthName {
@@ -73,6 +73,58 @@ impl MonthName {
     }
 }
 
+/// A day in the calendar, for remembering when things happened
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Date {
+    pub year: u32,
+    pub month: MonthName,
+    pub month_day: u32,
+    pub year_day: u32,
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}, Year {}", self.month_day, self.month, self.year)
+    }
+}
+
+/// "1st", "2nd", "3rd", "4th", ...
+pub fn ordinal(n: u32) -> String {
+    let suffix = match (n % 10, n % 100) {
+        (1, 11) | (2, 12) | (3, 13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{n}{suffix}")
+}
+
+/// The current reign, for dating by regnal year. Only present while someone sits on the throne.
+#[derive(Resource, Debug)]
+pub struct Reign {
+    /// How the monarch is known, e.g. "Queen Paulina"
+    pub monarch: String,
+    pub since: Date,
+}
+
+impl Reign {
+    /// Which year of the reign a date falls in, counting the year of accession as the first
+    pub fn regnal_year(&self, date: Date) -> u32 {
+        let anniversary_passed = date.year_day >= self.since.year_day;
+        (date.year - self.since.year + u32::from(anniversary_passed)).max(1)
+    }
+
+    /// e.g. "the 4th year of the reign of Queen Paulina"
+    pub fn describe(&self, date: Date) -> String {
+        format!(
+            "the {} year of the reign of {}",
+            ordinal(self.regnal_year(date)),
+            self.monarch
+        )
+    }
+}
+
 #[derive(Component, Debug)]
 pub struct Calendar {
     year: u32,
@@ -101,6 +153,20 @@ impl Calendar {
             month_map,
         }
     }
+
+    pub fn date(&self) -> Date {
+        Date {
+            year: self.year,
+            month: self.month,
+            month_day: self.month_day,
+            year_day: self.year_day,
+        }
+    }
+
+    /// Day of the year, starting from 1
+    pub fn year_day(&self) -> u32 {
+        self.year_day
+    }
 }
 
 impl fmt::Display for Calendar {
@@ -150,7 +216,7 @@ fn add_calendar(mut commands: Commands) {
     commands.spawn(calendar);
 }
 
-fn advance_date(mut query: Query<&mut Calendar>) {
+fn advance_date(mut query: Query<&mut Calendar>, reign: Option<Res<Reign>>) {
     let mut calendar = query.single_mut();
 
     handle_months(&mut calendar);
@@ -158,7 +224,11 @@ fn advance_date(mut query: Query<&mut Calendar>) {
     handle_years(&mut calendar);
 
     debug!("{:?}", calendar); // can probably remove this at some point
-    info!("{}", *calendar);
+    if let Some(reign) = reign {
+        info!("{}, {}", *calendar, reign.describe(calendar.date()));
+    } else {
+        info!("{}", *calendar);
+    }
 }
 
 fn handle_months(calendar: &mut Mut<'_, Calendar>) {
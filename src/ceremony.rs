@@ -0,0 +1,421 @@
+use std::fmt;
+
+use bevy::{prelude::*, utils::HashSet};
+
+use crate::{
+    calendar::{Calendar, Date},
+    dynasty::{Dynasty, InDynasty},
+    kinship::Kinship,
+    life::Alive,
+    marriage::MarriageEvent,
+    memories::{Memories, MemoryKind},
+    moon::{Moon, MoonHouse},
+    people::{AssignedMoonHouse, Name},
+    relationships::OpinionEvent,
+    reproduction::BirthEvent,
+    royalty::{Legitimacy, Prestige, Royalty, Title},
+    state::RunState,
+    succession::SuccessionEvent,
+    titles::LandedTitle,
+};
+
+const CORONATION_LEGITIMACY: i32 = 20;
+const WEDDING_LEGITIMACY: i32 = 5;
+const CHRISTENING_LEGITIMACY: i32 = 5;
+const FUNERAL_LEGITIMACY: i32 = 5;
+/// Extra legitimacy for holding a ceremony on an auspicious day, lost for an inauspicious one
+const AUSPICIOUS_LEGITIMACY: i32 = 5;
+const ATTENDANCE_OPINION: i32 = 5;
+/// Extra opinion for a ceremony on an auspicious day, lost for an inauspicious one
+const AUSPICIOUS_OPINION: i32 = 5;
+/// Renown gained by those a ceremony is held for
+const HONOURED_PRESTIGE: i32 = 10;
+const HONOURED_WEIGHT: f64 = 0.6;
+const ATTENDED_WEIGHT: f64 = 0.2;
+
+/// The great occasions of royal life
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CeremonyKind {
+    Coronation,
+    Wedding,
+    Christening,
+    Funeral,
+}
+
+impl CeremonyKind {
+    /// How many days the court will wait for an auspicious day before going ahead anyway
+    pub fn patience(self) -> u32 {
+        match self {
+            CeremonyKind::Coronation => 90,
+            CeremonyKind::Wedding => 60,
+            CeremonyKind::Christening => 30,
+            CeremonyKind::Funeral => 7,
+        }
+    }
+
+    /// How much the ceremony adds to the reigning monarch's legitimacy
+    fn legitimacy(self) -> i32 {
+        match self {
+            CeremonyKind::Coronation => CORONATION_LEGITIMACY,
+            CeremonyKind::Wedding => WEDDING_LEGITIMACY,
+            CeremonyKind::Christening => CHRISTENING_LEGITIMACY,
+            CeremonyKind::Funeral => FUNERAL_LEGITIMACY,
+        }
+    }
+
+    /// e.g. "Albert Morales-Allan was crowned"
+    fn story(self, honoured: &[&Name]) -> String {
+        let names: Vec<String> = honoured
+            .iter()
+            .map(|name| format!("{} {}", name.first, name.last))
+            .collect();
+        let names = names.join(" and ");
+        match self {
+            CeremonyKind::Coronation => format!("{names} was crowned"),
+            CeremonyKind::Wedding => format!("{names} were wed"),
+            CeremonyKind::Christening => format!("{names} was christened"),
+            CeremonyKind::Funeral => format!("{names} was laid to rest"),
+        }
+    }
+}
+
+impl fmt::Display for CeremonyKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self {
+            CeremonyKind::Coronation => "coronation",
+            CeremonyKind::Wedding => "wedding",
+            CeremonyKind::Christening => "christening",
+            CeremonyKind::Funeral => "funeral",
+        };
+        write!(f, "{kind}")
+    }
+}
+
+/// A ceremony waiting for its day
+#[derive(Debug)]
+pub struct Ceremony {
+    pub kind: CeremonyKind,
+    /// Who the ceremony is for, e.g. the couple at a wedding
+    pub honoured: Vec<Entity>,
+    /// The High House whose ascendancy makes for an auspicious day
+    pub house: MoonHouse,
+    pub announced: Date,
+    pub days_waited: u32,
+}
+
+/// Ceremonies announced but not yet held
+#[derive(Resource, Debug, Default)]
+pub struct CourtCalendar {
+    pub ceremonies: Vec<Ceremony>,
+}
+
+/// A ceremony has been held
+#[derive(Event, Debug)]
+pub struct CeremonyEvent {
+    kind: CeremonyKind,
+    honoured: Vec<Entity>,
+    attendees: Vec<Entity>,
+    auspicious: bool,
+}
+
+impl CeremonyEvent {
+    pub fn new(
+        kind: CeremonyKind,
+        honoured: Vec<Entity>,
+        attendees: Vec<Entity>,
+        auspicious: bool,
+    ) -> Self {
+        CeremonyEvent {
+            kind,
+            honoured,
+            attendees,
+            auspicious,
+        }
+    }
+
+    pub fn kind(&self) -> CeremonyKind {
+        self.kind
+    }
+
+    pub fn honoured(&self) -> &[Entity] {
+        &self.honoured
+    }
+
+    pub fn attendees(&self) -> &[Entity] {
+        &self.attendees
+    }
+
+    pub fn auspicious(&self) -> bool {
+        self.auspicious
+    }
+}
+
+/// Announce ceremonies for the royal family's successions, weddings and births
+fn announce_ceremonies(
+    mut court_calendar: ResMut<CourtCalendar>,
+    mut ev_succession: EventReader<SuccessionEvent>,
+    mut ev_marriage: EventReader<MarriageEvent>,
+    mut ev_birth: EventReader<BirthEvent>,
+    query_calendar: Query<&Calendar>,
+    query_people: Query<(
+        Option<&Royalty>,
+        Option<&InDynasty>,
+        &AssignedMoonHouse,
+        Has<Alive>,
+    )>,
+    query_dynasties: Query<&Dynasty>,
+) {
+    let date = query_calendar.single().date();
+    let is_royal = |person: Entity| {
+        query_people
+            .get(person)
+            .is_ok_and(|(royalty, ..)| royalty.is_some())
+    };
+    // the dynasty's house, or the person's own if they don't have one
+    let patron_house = |person: Entity| {
+        query_people
+            .get(person)
+            .ok()
+            .map(|(_, member, assigned_house, _)| {
+                member
+                    .and_then(|member| query_dynasties.get(member.dynasty).ok())
+                    .map_or_else(
+                        || assigned_house.house.clone(),
+                        |dynasty| dynasty.house.clone(),
+                    )
+            })
+    };
+
+    let mut announced = Vec::new();
+    for event in ev_succession.read() {
+        // a deposed monarch lives on without a funeral
+        let buried = query_people
+            .get(event.predecessor())
+            .is_ok_and(|(.., alive)| !alive);
+        if buried {
+            announced.push((CeremonyKind::Funeral, vec![event.predecessor()]));
+        }
+        announced.push((CeremonyKind::Coronation, vec![event.heir()]));
+    }
+    for event in ev_marriage.read() {
+        if is_royal(event.first()) || is_royal(event.second()) {
+            announced.push((CeremonyKind::Wedding, vec![event.first(), event.second()]));
+        }
+    }
+    for event in ev_birth.read() {
+        let royal_parent = [event.mother(), event.father()].into_iter().any(|parent| {
+            query_people.get(parent).is_ok_and(|(royalty, ..)| {
+                royalty
+                    .is_some_and(|royalty| matches!(royalty.title, Title::Monarch | Title::Consort))
+            })
+        });
+        if royal_parent {
+            announced.push((CeremonyKind::Christening, vec![event.child()]));
+        }
+    }
+
+    for (kind, honoured) in announced {
+        let Some(house) = patron_house(honoured[0]) else {
+            continue;
+        };
+        info!(
+            "The court announces a royal {}, to be held when House {} is ascendant",
+            kind, house
+        );
+        court_calendar.ceremonies.push(Ceremony {
+            kind,
+            honoured,
+            house,
+            announced: date,
+            days_waited: 0,
+        });
+    }
+}
+
+/// Hold each ceremony on the first auspicious day, or once the court has waited long enough.
+/// The royals, the title holders and the family of those honoured all attend.
+fn hold_ceremonies(
+    mut court_calendar: ResMut<CourtCalendar>,
+    mut ev_ceremony: EventWriter<CeremonyEvent>,
+    kinship: Kinship,
+    query_calendar: Query<&Calendar>,
+    query_moon: Query<&Moon>,
+    query_titles: Query<&LandedTitle>,
+    query_people: Query<(Entity, &Name, Has<Alive>, Option<&Royalty>)>,
+) {
+    let date = query_calendar.single().date();
+    let moon = query_moon.single();
+
+    let mut waiting = Vec::new();
+    for mut ceremony in court_calendar.ceremonies.drain(..) {
+        // only a funeral goes ahead without the living to honour
+        let honoured_alive = ceremony.honoured.iter().all(|person| {
+            query_people
+                .get(*person)
+                .is_ok_and(|(_, _, alive, _)| alive)
+        });
+        // nor is anyone crowned who has lost the throne in the meantime
+        let deposed = ceremony.kind == CeremonyKind::Coronation
+            && ceremony.honoured.iter().any(|person| {
+                query_people.get(*person).is_ok_and(|(.., royalty)| {
+                    royalty.is_none_or(|royalty| royalty.title != Title::Monarch)
+                })
+            });
+        if (ceremony.kind != CeremonyKind::Funeral && !honoured_alive) || deposed {
+            info!("The royal {} was called off", ceremony.kind);
+            continue;
+        }
+
+        let auspicious = moon.house == ceremony.house;
+        if !auspicious && ceremony.days_waited < ceremony.kind.patience() {
+            ceremony.days_waited += 1;
+            waiting.push(ceremony);
+            continue;
+        }
+
+        let mut attendees: HashSet<Entity> = query_people
+            .iter()
+            .filter(|(_, _, alive, royalty)| *alive && royalty.is_some())
+            .map(|(person, ..)| person)
+            .collect();
+        attendees.extend(query_titles.iter().filter_map(|title| title.holder));
+        for person in &ceremony.honoured {
+            attendees.extend(kinship.close_kin(*person));
+        }
+        let attendees: Vec<Entity> = attendees
+            .into_iter()
+            .filter(|person| {
+                !ceremony.honoured.contains(person)
+                    && query_people
+                        .get(*person)
+                        .is_ok_and(|(_, _, alive, _)| alive)
+            })
+            .collect();
+
+        let honoured: Vec<&Name> = ceremony
+            .honoured
+            .iter()
+            .filter_map(|person| query_people.get(*person).ok())
+            .map(|(_, name, _, _)| name)
+            .collect();
+        let omen = if auspicious {
+            format!("under the ascendant House {}", ceremony.house)
+        } else {
+            format!("though House {} was not ascendant", ceremony.house)
+        };
+        info!(
+            "On {}, {}, {} before {} guests, {} days after it was announced on {}",
+            date,
+            omen,
+            ceremony.kind.story(&honoured),
+            attendees.len(),
+            ceremony.days_waited,
+            ceremony.announced
+        );
+
+        ev_ceremony.send(CeremonyEvent::new(
+            ceremony.kind,
+            ceremony.honoured,
+            attendees,
+            auspicious,
+        ));
+    }
+    court_calendar.ceremonies = waiting;
+}
+
+/// Ceremonies shore up the monarch, bring renown to those honoured, warm the guests to them, and
+/// are remembered
+fn honour_ceremonies(
+    mut ev_ceremony: EventReader<CeremonyEvent>,
+    mut ev_opinion: EventWriter<OpinionEvent>,
+    query_calendar: Query<&Calendar>,
+    mut query_monarchs: Query<(Entity, &Royalty, &mut Legitimacy), With<Alive>>,
+    mut query_memories: Query<(&mut Memories, &mut Prestige, Has<Alive>)>,
+) {
+    let date = query_calendar.single().date();
+
+    for event in ev_ceremony.read() {
+        let omen = if event.auspicious() { 1 } else { -1 };
+        let mut monarch = None;
+        for (person, royalty, mut legitimacy) in &mut query_monarchs {
+            if royalty.title == Title::Monarch {
+                legitimacy.adjust(event.kind().legitimacy() + omen * AUSPICIOUS_LEGITIMACY);
+                monarch = Some(person);
+            }
+        }
+
+        // the guests warm to whoever the ceremony was for, or to the monarch who hosted a funeral
+        let living: Vec<Entity> = event
+            .honoured()
+            .iter()
+            .copied()
+            .filter(|person| query_memories.get(*person).is_ok_and(|(.., alive)| alive))
+            .collect();
+        for person in &living {
+            if let Ok((_, mut prestige, _)) = query_memories.get_mut(*person) {
+                prestige.adjust(HONOURED_PRESTIGE);
+            }
+        }
+        let warmed_to = if living.is_empty() {
+            monarch.into_iter().collect()
+        } else {
+            living.clone()
+        };
+        let reason = match event.kind() {
+            CeremonyKind::Coronation => "attended their coronation",
+            CeremonyKind::Wedding => "attended their wedding",
+            CeremonyKind::Christening => "attended their christening",
+            CeremonyKind::Funeral => "attended the funeral they held",
+        };
+        for attendee in event.attendees() {
+            for honoured in &warmed_to {
+                ev_opinion.send(OpinionEvent::new(
+                    *attendee,
+                    *honoured,
+                    ATTENDANCE_OPINION + omen * AUSPICIOUS_OPINION,
+                    reason,
+                ));
+            }
+        }
+
+        let sign = if event.kind() == CeremonyKind::Funeral {
+            -1.0
+        } else {
+            1.0
+        };
+        let about = event.honoured().first().copied();
+        for (people, honoured, weight) in [
+            (&living, true, HONOURED_WEIGHT),
+            (&event.attendees().to_vec(), false, ATTENDED_WEIGHT),
+        ] {
+            for person in people {
+                if let Ok((mut memories, ..)) = query_memories.get_mut(*person) {
+                    memories.remember(
+                        MemoryKind::Ceremony {
+                            ceremony: event.kind(),
+                            honoured,
+                        },
+                        about,
+                        date,
+                        sign * weight,
+                    );
+                }
+            }
+        }
+    }
+}
+
+pub struct CeremonyPlugin;
+
+impl Plugin for CeremonyPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CourtCalendar>()
+            .add_event::<CeremonyEvent>()
+            .add_systems(
+                Update,
+                (announce_ceremonies, hold_ceremonies, honour_ceremonies)
+                    .chain()
+                    .run_if(in_state(RunState::Running)),
+            );
+    }
+}
@@ -0,0 +1,688 @@
+use std::fmt;
+
+use bevy::prelude::*;
+use rand::prelude::*;
+
+use crate::{
+    calendar::Calendar,
+    crisis::BacksClaimant,
+    faction::FactionMember,
+    life::{Alive, DeathEvent},
+    marriage::Spouse,
+    memories::{Memories, MemoryKind},
+    naming::Culture,
+    people::Name,
+    personality::Personality,
+    regency::Regent,
+    relationships::{OpinionEvent, Relationships},
+    royalty::{Legitimacy, Prestige, Royalty, Title},
+    scheme::{SchemeEvent, SchemeKind, SchemeOutcome},
+    social::{Interaction, SocialEvent},
+    state::RunState,
+    titles::{Acquisition, LandedTitle, TitleRank},
+    traits::{Attribute, Traits},
+};
+
+/// Chance someone insulted loses their temper and strikes back, for the most hot-headed
+const ASSAULT_CHANCE: f64 = 0.05;
+/// Chance a poisoning is suspected to be murder at all
+const POISON_SUSPICION_CHANCE: f64 = 0.5;
+/// Chance a spouse finds out about an affair
+const ADULTERY_DISCOVERY_CHANCE: f64 = 0.3;
+/// How sure the accuser can be of what happened, from 0 to 1
+const THEFT_EVIDENCE: f64 = 0.5;
+const ASSAULT_EVIDENCE: f64 = 0.8;
+const POISON_EVIDENCE: f64 = 0.3;
+const PLOT_EVIDENCE: f64 = 0.9;
+const ADULTERY_EVIDENCE: f64 = 0.6;
+/// Days between a crime being reported and the case being heard
+const HEARING_DELAY: u32 = 7;
+/// Days in prison for each point of a crime's severity
+const IMPRISONMENT_DAYS: u32 = 180;
+const FINE_PRESTIGE: i32 = -30;
+/// Part of a fine paid to the victim
+const COMPENSATION_PRESTIGE: i32 = 15;
+const CONVICTED_OPINION: i32 = -30;
+const JUSTICE_OPINION: i32 = 15;
+const DENIED_JUSTICE_OPINION: i32 = -20;
+const CONVICTED_WEIGHT: f64 = -0.6;
+const ACQUITTED_WEIGHT: f64 = 0.2;
+const JUSTICE_WEIGHT: f64 = 0.3;
+const DENIED_JUSTICE_WEIGHT: f64 = -0.4;
+
+/// A wrong the law takes an interest in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrimeKind {
+    Theft,
+    Assault,
+    AttemptedMurder,
+    Murder,
+    Adultery,
+    /// Renouncing the High Houses
+    Heresy,
+    /// Plotting against the royal family
+    Treason,
+}
+
+impl CrimeKind {
+    /// How grave the crime is, from 1 to 4
+    fn severity(self) -> u32 {
+        match self {
+            CrimeKind::Theft => 1,
+            CrimeKind::Assault | CrimeKind::Adultery | CrimeKind::Heresy => 2,
+            CrimeKind::AttemptedMurder => 3,
+            CrimeKind::Murder | CrimeKind::Treason => 4,
+        }
+    }
+
+    /// Only the monarch can try the gravest crimes
+    fn is_capital(self) -> bool {
+        matches!(
+            self,
+            CrimeKind::AttemptedMurder | CrimeKind::Murder | CrimeKind::Treason
+        )
+    }
+}
+
+impl fmt::Display for CrimeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self {
+            CrimeKind::Theft => "theft",
+            CrimeKind::Assault => "assault",
+            CrimeKind::AttemptedMurder => "attempted murder",
+            CrimeKind::Murder => "murder",
+            CrimeKind::Adultery => "adultery",
+            CrimeKind::Heresy => "heresy",
+            CrimeKind::Treason => "treason",
+        };
+        write!(f, "{kind}")
+    }
+}
+
+/// What a judge hands down to the guilty
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sentence {
+    Fine,
+    Imprisonment,
+    Exile,
+    Execution,
+}
+
+impl fmt::Display for Sentence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sentence = match self {
+            Sentence::Fine => "fined",
+            Sentence::Imprisonment => "imprisoned",
+            Sentence::Exile => "exiled",
+            Sentence::Execution => "sentenced to death",
+        };
+        write!(f, "{sentence}")
+    }
+}
+
+/// Locked away until their sentence is served
+#[derive(Component, Debug)]
+pub struct Imprisoned {
+    pub days: u32,
+}
+
+/// Banished from the realm, stripped of rank, lands and any claim to the throne
+#[derive(Component, Debug)]
+pub struct Exiled;
+
+/// A crime waiting to be heard
+#[derive(Component, Debug)]
+pub struct Case {
+    pub crime: CrimeKind,
+    pub offender: Entity,
+    pub victim: Entity,
+    pub judge: Entity,
+    /// How strong the case against the offender is, from 0 to 1
+    pub evidence: f64,
+    /// Days until the hearing
+    pub hearing: u32,
+}
+
+/// Someone has been wronged and it's come to light
+#[derive(Event, Debug)]
+pub struct CrimeEvent {
+    kind: CrimeKind,
+    offender: Entity,
+    victim: Entity,
+    evidence: f64,
+}
+
+impl CrimeEvent {
+    pub fn new(kind: CrimeKind, offender: Entity, victim: Entity, evidence: f64) -> Self {
+        CrimeEvent {
+            kind,
+            offender,
+            victim,
+            evidence,
+        }
+    }
+
+    pub fn kind(&self) -> CrimeKind {
+        self.kind
+    }
+
+    pub fn offender(&self) -> Entity {
+        self.offender
+    }
+
+    pub fn victim(&self) -> Entity {
+        self.victim
+    }
+
+    pub fn evidence(&self) -> f64 {
+        self.evidence
+    }
+}
+
+/// A case has been heard
+#[derive(Event, Debug)]
+pub struct VerdictEvent {
+    crime: CrimeKind,
+    offender: Entity,
+    victim: Entity,
+    judge: Entity,
+    sentence: Option<Sentence>,
+}
+
+impl VerdictEvent {
+    pub fn new(
+        crime: CrimeKind,
+        offender: Entity,
+        victim: Entity,
+        judge: Entity,
+        sentence: Option<Sentence>,
+    ) -> Self {
+        VerdictEvent {
+            crime,
+            offender,
+            victim,
+            judge,
+            sentence,
+        }
+    }
+
+    pub fn crime(&self) -> CrimeKind {
+        self.crime
+    }
+
+    pub fn offender(&self) -> Entity {
+        self.offender
+    }
+
+    pub fn victim(&self) -> Entity {
+        self.victim
+    }
+
+    pub fn judge(&self) -> Entity {
+        self.judge
+    }
+
+    /// What the offender got, or `None` if they were acquitted
+    pub fn sentence(&self) -> Option<Sentence> {
+        self.sentence
+    }
+}
+
+/// Insults come to blows, thefts are reported, and exposed plots, suspicious deaths and
+/// discovered affairs all become crimes
+fn commit_crimes(
+    mut ev_social: EventReader<SocialEvent>,
+    mut ev_scheme: EventReader<SchemeEvent>,
+    mut ev_crime: EventWriter<CrimeEvent>,
+    query_people: Query<(&Traits, Option<&Spouse>, Has<Royalty>)>,
+) {
+    let mut rng = thread_rng();
+    let is_royal = |person: Entity| query_people.get(person).is_ok_and(|(.., royal)| royal);
+
+    for event in ev_social.read() {
+        let (initiator, target) = (event.initiator(), event.target());
+        match event.interaction() {
+            Interaction::Theft => {
+                ev_crime.send(CrimeEvent::new(
+                    CrimeKind::Theft,
+                    initiator,
+                    target,
+                    THEFT_EVIDENCE,
+                ));
+            }
+            Interaction::Insult => {
+                let temper = query_people
+                    .get(target)
+                    .map_or(0.0, |(traits, ..)| traits.attribute(Attribute::Temper));
+                if rng.gen_bool(ASSAULT_CHANCE * temper) {
+                    ev_crime.send(CrimeEvent::new(
+                        CrimeKind::Assault,
+                        target,
+                        initiator,
+                        ASSAULT_EVIDENCE,
+                    ));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    for event in ev_scheme.read() {
+        let (plotter, target) = (event.plotter(), event.target());
+        match (event.kind(), event.outcome()) {
+            (SchemeKind::Assassination, SchemeOutcome::Succeeded)
+                if rng.gen_bool(POISON_SUSPICION_CHANCE) =>
+            {
+                ev_crime.send(CrimeEvent::new(
+                    CrimeKind::Murder,
+                    plotter,
+                    target,
+                    POISON_EVIDENCE,
+                ));
+            }
+            // plotting murder or blackmail against a royal is treason
+            (SchemeKind::Assassination | SchemeKind::Blackmail, SchemeOutcome::Exposed)
+                if is_royal(target) =>
+            {
+                ev_crime.send(CrimeEvent::new(
+                    CrimeKind::Treason,
+                    plotter,
+                    target,
+                    PLOT_EVIDENCE,
+                ));
+            }
+            (SchemeKind::Assassination, SchemeOutcome::Exposed) => {
+                ev_crime.send(CrimeEvent::new(
+                    CrimeKind::AttemptedMurder,
+                    plotter,
+                    target,
+                    PLOT_EVIDENCE,
+                ));
+            }
+            (SchemeKind::Seduction, SchemeOutcome::Succeeded) => {
+                // each lover who is married to someone else has wronged their spouse
+                for (lover, other) in [(plotter, target), (target, plotter)] {
+                    let spouse = query_people
+                        .get(lover)
+                        .ok()
+                        .and_then(|(_, spouse, _)| spouse)
+                        .map(|spouse| spouse.entity)
+                        .filter(|spouse| *spouse != other);
+                    if let Some(spouse) = spouse {
+                        if rng.gen_bool(ADULTERY_DISCOVERY_CHANCE) {
+                            ev_crime.send(CrimeEvent::new(
+                                CrimeKind::Adultery,
+                                lover,
+                                spouse,
+                                ADULTERY_EVIDENCE,
+                            ));
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Crimes go before the highest lord of the victim's people, or before the monarch when they're
+/// capital or a royal or lord is caught up in them. The monarch answers to no one.
+fn open_cases(
+    mut commands: Commands,
+    mut ev_crime: EventReader<CrimeEvent>,
+    relationships: Res<Relationships>,
+    query_people: Query<(&Culture, Option<&Royalty>)>,
+    query_titles: Query<&LandedTitle>,
+) {
+    let monarch = query_titles
+        .iter()
+        .find(|title| title.rank == TitleRank::Kingdom)
+        .and_then(|title| title.holder);
+    let is_lord = |person: Entity| {
+        query_titles
+            .iter()
+            .any(|title| title.holder == Some(person))
+    };
+    let is_royal = |person: Entity| {
+        query_people
+            .get(person)
+            .is_ok_and(|(_, royalty)| royalty.is_some())
+    };
+
+    for event in ev_crime.read() {
+        let (offender, victim) = (event.offender(), event.victim());
+        if Some(offender) == monarch {
+            debug!("The monarch is above the law");
+            continue;
+        }
+
+        let Ok((culture, _)) = query_people.get(victim) else {
+            continue;
+        };
+        let local_lord = query_titles
+            .iter()
+            .filter(|title| title.rank != TitleRank::Kingdom)
+            .filter_map(|title| Some((title.rank, title.holder?)))
+            .filter(|(_, lord)| {
+                *lord != offender
+                    && *lord != victim
+                    && query_people
+                        .get(*lord)
+                        .is_ok_and(|(lord_culture, _)| lord_culture == culture)
+            })
+            .min_by_key(|(rank, lord)| (*rank, -relationships.opinion(*lord, victim)))
+            .map(|(_, lord)| lord);
+        let high_justice = event.kind().is_capital()
+            || [offender, victim]
+                .into_iter()
+                .any(|person| is_royal(person) || is_lord(person));
+        let judge = if high_justice {
+            monarch
+        } else {
+            local_lord.or(monarch)
+        };
+        let Some(judge) = judge else {
+            continue;
+        };
+
+        commands.spawn(Case {
+            crime: event.kind(),
+            offender,
+            victim,
+            judge,
+            evidence: event.evidence(),
+            hearing: HEARING_DELAY,
+        });
+    }
+}
+
+/// How likely a judge is to hand down each sentence. Cruel judges are harsh, the pious harsher
+/// still on adulterers and heretics, and the gravest crimes carry the gravest sentences.
+fn sentence_weights(crime: CrimeKind, judge: &Personality) -> [(Sentence, f64); 4] {
+    let harshness = if matches!(crime, CrimeKind::Adultery | CrimeKind::Heresy) {
+        judge.cruelty.max(judge.piety)
+    } else {
+        judge.cruelty
+    };
+    let severity = f64::from(crime.severity());
+    [
+        (Sentence::Fine, (4.0 - severity) * (1.5 - harshness)),
+        (Sentence::Imprisonment, 1.0 + severity * 0.5),
+        (Sentence::Exile, (severity - 1.0) * harshness),
+        (
+            Sentence::Execution,
+            (severity - 2.0).max(0.0) * harshness * 2.0,
+        ),
+    ]
+}
+
+/// Cases are heard once their day comes. Whether the offender is convicted depends on the
+/// evidence and on who the judge favours, and the sentence on the judge's temperament.
+fn hear_cases(
+    mut commands: Commands,
+    relationships: Res<Relationships>,
+    mut query_cases: Query<(Entity, &mut Case)>,
+    query_judges: Query<&Personality, With<Alive>>,
+    query_offenders: Query<(), (With<Alive>, Without<Exiled>)>,
+    mut ev_verdict: EventWriter<VerdictEvent>,
+) {
+    let mut rng = thread_rng();
+
+    for (entity, mut case) in &mut query_cases {
+        if case.hearing > 0 {
+            case.hearing -= 1;
+            continue;
+        }
+        commands.entity(entity).despawn();
+
+        let Ok(judge) = query_judges.get(case.judge) else {
+            continue;
+        };
+        if !query_offenders.contains(case.offender) {
+            // dead or fled beyond the law's reach
+            continue;
+        }
+
+        let bias = f64::from(
+            relationships.opinion(case.judge, case.victim)
+                - relationships.opinion(case.judge, case.offender),
+        ) / 200.0;
+        let conviction_chance = (case.evidence + bias).clamp(0.05, 0.95);
+        let sentence = rng
+            .gen_bool(conviction_chance)
+            .then(|| {
+                sentence_weights(case.crime, judge)
+                    .choose_weighted(&mut rng, |(_, weight)| *weight)
+                    .map(|(sentence, _)| *sentence)
+                    .ok()
+            })
+            .flatten();
+
+        ev_verdict.send(VerdictEvent::new(
+            case.crime,
+            case.offender,
+            case.victim,
+            case.judge,
+            sentence,
+        ));
+    }
+}
+
+/// Sentences are carried out, and the offender and victim remember how justice treated them
+fn carry_out_sentences(
+    mut commands: Commands,
+    mut ev_verdict: EventReader<VerdictEvent>,
+    mut ev_death: EventWriter<DeathEvent>,
+    mut ev_opinion: EventWriter<OpinionEvent>,
+    query_calendar: Query<&Calendar>,
+    mut query_people: Query<(&mut Memories, &mut Prestige), With<Alive>>,
+) {
+    let date = query_calendar.single().date();
+
+    for event in ev_verdict.read() {
+        let (offender, victim, judge, crime) = (
+            event.offender(),
+            event.victim(),
+            event.judge(),
+            event.crime(),
+        );
+        let convicted = event.sentence().is_some();
+
+        match event.sentence() {
+            Some(Sentence::Fine) => {
+                if let Ok((_, mut prestige)) = query_people.get_mut(offender) {
+                    prestige.adjust(FINE_PRESTIGE);
+                }
+                if let Ok((_, mut prestige)) = query_people.get_mut(victim) {
+                    prestige.adjust(COMPENSATION_PRESTIGE);
+                }
+            }
+            Some(Sentence::Imprisonment) => {
+                commands.entity(offender).insert(Imprisoned {
+                    days: IMPRISONMENT_DAYS * crime.severity(),
+                });
+            }
+            Some(Sentence::Exile) => {
+                // a regent in exile rules no one, so the young monarch gets another
+                commands.entity(offender).insert(Exiled).remove::<(
+                    Royalty,
+                    Legitimacy,
+                    Regent,
+                    BacksClaimant,
+                    FactionMember,
+                )>();
+            }
+            Some(Sentence::Execution) => {
+                ev_death.send(DeathEvent::new(offender, &format!("Executed for {crime}")));
+            }
+            None => {}
+        }
+
+        if convicted {
+            ev_opinion.send(OpinionEvent::new(
+                offender,
+                judge,
+                CONVICTED_OPINION,
+                "convicted them",
+            ));
+            ev_opinion.send(OpinionEvent::new(
+                victim,
+                judge,
+                JUSTICE_OPINION,
+                "saw justice done",
+            ));
+        } else {
+            ev_opinion.send(OpinionEvent::new(
+                victim,
+                judge,
+                DENIED_JUSTICE_OPINION,
+                "let the guilty go free",
+            ));
+        }
+        let (offender_weight, victim_weight) = if convicted {
+            (CONVICTED_WEIGHT, JUSTICE_WEIGHT)
+        } else {
+            (ACQUITTED_WEIGHT, DENIED_JUSTICE_WEIGHT)
+        };
+        for (person, other, accused, weight) in [
+            (offender, victim, true, offender_weight),
+            (victim, offender, false, victim_weight),
+        ] {
+            if let Ok((mut memories, _)) = query_people.get_mut(person) {
+                memories.remember(
+                    MemoryKind::Trial {
+                        crime,
+                        accused,
+                        convicted,
+                    },
+                    Some(other),
+                    date,
+                    weight,
+                );
+            }
+        }
+    }
+}
+
+/// The exiled forfeit their titles to their liege
+fn forfeit_titles(
+    mut ev_verdict: EventReader<VerdictEvent>,
+    query_calendar: Query<&Calendar>,
+    mut query_titles: Query<(Entity, &mut LandedTitle)>,
+    query_names: Query<&Name>,
+) {
+    let date = query_calendar.single().date();
+
+    for event in ev_verdict.read() {
+        if event.sentence() != Some(Sentence::Exile) {
+            continue;
+        }
+        let exiled = event.offender();
+
+        let lieges: Vec<(Entity, Option<Entity>)> = query_titles
+            .iter()
+            .map(|(title, details)| (title, details.holder))
+            .collect();
+        for (_, mut title) in &mut query_titles {
+            if title.holder != Some(exiled) {
+                continue;
+            }
+            title.vacate(date, "was exiled");
+
+            let Some((liege, name)) = lieges
+                .iter()
+                .find(|(liege, _)| Some(*liege) == title.liege)
+                .and_then(|(_, holder)| *holder)
+                .filter(|holder| *holder != exiled)
+                .and_then(|holder| Some((holder, query_names.get(holder).ok()?)))
+            else {
+                continue;
+            };
+            title.grant(liege, &name.first, date, Acquisition::Escheat);
+            info!("The {} passes to {} {}", *title, name.first, name.last);
+        }
+    }
+}
+
+/// Prisoners are let out once they've served their time
+fn release_prisoners(mut commands: Commands, mut query: Query<(Entity, &Name, &mut Imprisoned)>) {
+    for (person, name, mut imprisoned) in &mut query {
+        imprisoned.days = imprisoned.days.saturating_sub(1);
+        if imprisoned.days == 0 {
+            commands.entity(person).remove::<Imprisoned>();
+            info!("{} {} is released from prison", name.first, name.last);
+        }
+    }
+}
+
+/// Spread word of how cases were decided
+fn tell_crime_stories(
+    mut ev_verdict: EventReader<VerdictEvent>,
+    query_names: Query<&Name>,
+    query_royals: Query<&Royalty>,
+) {
+    for event in ev_verdict.read() {
+        let Ok([offender, victim, judge]) =
+            query_names.get_many([event.offender(), event.victim(), event.judge()])
+        else {
+            continue;
+        };
+        let court = if query_royals
+            .get(event.judge())
+            .is_ok_and(|royalty| royalty.title == Title::Monarch)
+        {
+            "the royal court"
+        } else {
+            "the court"
+        };
+        if let Some(sentence) = event.sentence() {
+            warn!(
+                "{} {} is {} by {} of {} {} for {} against {} {}",
+                offender.first,
+                offender.last,
+                sentence,
+                court,
+                judge.first,
+                judge.last,
+                event.crime(),
+                victim.first,
+                victim.last
+            );
+        } else {
+            info!(
+                "{} {} is acquitted by {} of {} {} of {} against {} {}",
+                offender.first,
+                offender.last,
+                court,
+                judge.first,
+                judge.last,
+                event.crime(),
+                victim.first,
+                victim.last
+            );
+        }
+    }
+}
+
+pub struct CrimePlugin;
+
+impl Plugin for CrimePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<CrimeEvent>()
+            .add_event::<VerdictEvent>()
+            .add_systems(
+                Update,
+                (
+                    commit_crimes,
+                    open_cases,
+                    hear_cases,
+                    carry_out_sentences,
+                    forfeit_titles,
+                    release_prisoners,
+                    tell_crime_stories,
+                )
+                    .chain()
+                    .run_if(in_state(RunState::Running)),
+            );
+    }
+}
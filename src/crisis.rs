@@ -0,0 +1,475 @@
+use bevy::{ecs::system::SystemParam, prelude::*, utils::HashMap};
+use rand::prelude::*;
+
+use crate::{
+    calendar::{Calendar, Date},
+    kinship::Kinship,
+    life::{Alive, DeathEvent, PerilEvent},
+    people::Name,
+    personality::Personality,
+    relationships::{OpinionEvent, Relationships},
+    royalty::{Legitimacy, Prestige, Royalty, Title, PRESTIGE_LIMIT},
+    state::RunState,
+    succession::{Claimants, SuccessionEvent, SuccessionRules},
+    titles::LandedTitle,
+};
+
+/// How far down the line someone can be and still dispute the succession
+const RIVAL_DEPTH: usize = 4;
+/// A rival whose claim is at least this close to the heir's will dispute it
+const RIVAL_MARGIN: f64 = 0.1;
+/// Only the ambitious press a claim
+const RIVAL_AMBITION: f64 = 0.4;
+/// How much each place further down the line weakens a claim
+const PRECEDENCE_DECAY: f64 = 0.7;
+const WORTHINESS_WEIGHT: f64 = 0.3;
+/// Few will back a child's claim
+const MINOR_PENALTY: f64 = 0.4;
+const DISPUTED_LEGITIMACY: i32 = -15;
+/// Strength a faction gains for each noble backing it, on top of their prestige
+const BACKER_STRENGTH: i32 = 50;
+/// Nobles favour claimants from their own family
+const KIN_OPINION: i32 = 20;
+/// Most the weakest claimant will be talked into renouncing on any day, for the most loyal
+const DAILY_NEGOTIATION_CHANCE: f64 = 0.03;
+/// Most a claimant will plot against a rival on any day, for the cruellest
+const DAILY_ASSASSINATION_CHANCE: f64 = 0.01;
+const ASSASSINATION_SUCCESS_CHANCE: f64 = 0.4;
+const DISCOVERED_OPINION: i32 = -50;
+const DISCOVERED_PRESTIGE: i32 = -50;
+/// How long the claimants talk before they take up arms
+const DAYS_BEFORE_WAR: u32 = 20;
+const DAILY_BATTLE_CHANCE: f64 = 1.0 / 10.0;
+/// Chance each backer of the losing side falls in a battle
+const BATTLE_DEATH_CHANCE: f64 = 0.1;
+/// Victories a claimant needs to crush a rival
+const DECISIVE_VICTORIES: u32 = 3;
+const VICTORY_PRESTIGE: i32 = 25;
+const VICTORY_LEGITIMACY: i32 = 5;
+const DEFEAT_PRESTIGE: i32 = -25;
+
+/// The throne is disputed between claimants with comparable claims
+#[derive(Component, Debug)]
+pub struct SuccessionCrisis {
+    /// Whoever holds the crown while the crisis lasts
+    pub reigning: Entity,
+    pub claimants: Vec<Entity>,
+    pub started: Date,
+    pub days: u32,
+    pub at_war: bool,
+    /// Battles won by each claimant since they last crushed a rival
+    pub victories: HashMap<Entity, u32>,
+}
+
+/// A noble who has joined the faction of one of the claimants in a succession crisis
+#[derive(Component, Debug)]
+pub struct BacksClaimant {
+    pub claimant: Entity,
+}
+
+/// The factions in a succession crisis and the standing of their members
+#[derive(SystemParam)]
+pub struct Factions<'w, 's> {
+    query_backers: Query<'w, 's, (Entity, &'static BacksClaimant), With<Alive>>,
+    query_standing: Query<'w, 's, (&'static mut Prestige, Option<&'static mut Legitimacy>)>,
+}
+
+impl Factions<'_, '_> {
+    /// The living nobles backing a claimant
+    pub fn backers(&self, claimant: Entity) -> Vec<Entity> {
+        self.query_backers
+            .iter()
+            .filter(|(_, backing)| backing.claimant == claimant)
+            .map(|(backer, _)| backer)
+            .collect()
+    }
+
+    pub fn prestige(&self, person: Entity) -> i32 {
+        self.query_standing
+            .get(person)
+            .map_or(0, |(prestige, _)| prestige.score)
+    }
+
+    /// The claimant's own prestige, their backers' prestige and numbers, and the legitimacy of
+    /// their rule if they already hold the crown
+    pub fn strength(&self, claimant: Entity) -> i32 {
+        let legitimacy = self
+            .query_standing
+            .get(claimant)
+            .ok()
+            .and_then(|(_, legitimacy)| legitimacy.map(|legitimacy| legitimacy.score))
+            .unwrap_or(0);
+        let backing: i32 = self
+            .backers(claimant)
+            .into_iter()
+            .map(|backer| self.prestige(backer) + BACKER_STRENGTH)
+            .sum();
+        self.prestige(claimant) + backing + legitimacy
+    }
+
+    /// Change someone's prestige, and their legitimacy if they rule
+    pub fn adjust(&mut self, person: Entity, prestige: i32, legitimacy: i32) {
+        if let Ok((mut standing, ruling)) = self.query_standing.get_mut(person) {
+            standing.adjust(prestige);
+            if let Some(mut ruling) = ruling {
+                ruling.adjust(legitimacy);
+            }
+        }
+    }
+
+    /// The claimant with the weakest faction
+    fn weakest(&self, claimants: &[Entity]) -> Option<Entity> {
+        claimants
+            .iter()
+            .copied()
+            .min_by_key(|claimant| self.strength(*claimant))
+    }
+}
+
+/// How good a claim is, from its place in the line, the claimant's fitness to rule and renown
+fn claim_strength(claimants: &Claimants, person: Entity, position: usize, prestige: i32) -> f64 {
+    let minor = if claimants.is_adult(person) {
+        0.0
+    } else {
+        MINOR_PENALTY
+    };
+    PRECEDENCE_DECAY.powi(i32::try_from(position).unwrap_or(i32::MAX))
+        + claimants.worthiness(person) / 4.0 * WORTHINESS_WEIGHT
+        + f64::from(prestige) / f64::from(PRESTIGE_LIMIT)
+        - minor
+}
+
+/// After a succession, ambitious claimants nearly as entitled as the heir may dispute it
+fn detect_disputes(
+    mut commands: Commands,
+    mut ev_succession: EventReader<SuccessionEvent>,
+    rules: Res<SuccessionRules>,
+    claimants: Claimants,
+    query_crises: Query<&SuccessionCrisis>,
+    query_calendar: Query<&Calendar>,
+    query_people: Query<(&Name, &Personality, &Prestige)>,
+) {
+    for event in ev_succession.read() {
+        if event.is_contested() || !query_crises.is_empty() {
+            continue;
+        }
+
+        let ranked = claimants.ranked(event.predecessor(), rules.law);
+        let strength = |position: usize, person: Entity| {
+            let prestige = query_people
+                .get(person)
+                .map_or(0, |(_, _, prestige)| prestige.score);
+            claim_strength(&claimants, person, position, prestige)
+        };
+        let heir_strength = ranked
+            .iter()
+            .position(|person| *person == event.heir())
+            .map_or(0.0, |position| strength(position, event.heir()));
+        let rivals: Vec<Entity> = ranked
+            .iter()
+            .copied()
+            .enumerate()
+            .take(RIVAL_DEPTH)
+            .filter(|(position, person)| {
+                *person != event.heir()
+                    && claimants.is_adult(*person)
+                    && query_people
+                        .get(*person)
+                        .is_ok_and(|(_, personality, _)| personality.ambition > RIVAL_AMBITION)
+                    && strength(*position, *person) >= heir_strength - RIVAL_MARGIN
+            })
+            .map(|(_, person)| person)
+            .collect();
+        if rivals.is_empty() {
+            continue;
+        }
+
+        for rival in &rivals {
+            if let Ok((name, ..)) = query_people.get(*rival) {
+                warn!(
+                    "{} {} disputes the succession and claims the crown",
+                    name.first, name.last
+                );
+            }
+        }
+        commands.spawn(SuccessionCrisis {
+            reigning: event.heir(),
+            claimants: std::iter::once(event.heir()).chain(rivals).collect(),
+            started: query_calendar.single().date(),
+            days: 0,
+            at_war: false,
+            victories: HashMap::new(),
+        });
+    }
+}
+
+/// The royals and title holders each back the claimant they like best, kin most of all
+fn rally_factions(
+    mut commands: Commands,
+    relationships: Res<Relationships>,
+    kinship: Kinship,
+    query_crises: Query<&SuccessionCrisis>,
+    query_titles: Query<&LandedTitle>,
+    query_nobles: Query<(Entity, Has<Royalty>), (With<Alive>, Without<BacksClaimant>)>,
+    query_names: Query<&Name>,
+) {
+    for crisis in &query_crises {
+        for (noble, royal) in &query_nobles {
+            let holds_title = query_titles.iter().any(|title| title.holder == Some(noble));
+            if !(royal || holds_title) || crisis.claimants.contains(&noble) {
+                continue;
+            }
+
+            let kin = kinship.close_kin(noble);
+            let favourite = crisis.claimants.iter().copied().max_by_key(|claimant| {
+                let kin_bonus = if kin.contains(claimant) {
+                    KIN_OPINION
+                } else {
+                    0
+                };
+                relationships.opinion(noble, *claimant) + kin_bonus
+            });
+            let Some(claimant) = favourite else {
+                continue;
+            };
+
+            commands.entity(noble).insert(BacksClaimant { claimant });
+            if let (Ok(name), Ok(claimant_name)) =
+                (query_names.get(noble), query_names.get(claimant))
+            {
+                debug!(
+                    "{} {} backs the claim of {} {}",
+                    name.first, name.last, claimant_name.first, claimant_name.last
+                );
+            }
+        }
+    }
+}
+
+/// While the claimants talk, the weakest may be persuaded to renounce and the cruel turn to
+/// murder. If talking drags on too long it comes to war.
+fn press_claims(
+    mut factions: Factions,
+    mut query_crises: Query<&mut SuccessionCrisis>,
+    query_people: Query<(&Name, &Personality)>,
+    mut ev_death: EventWriter<DeathEvent>,
+    mut ev_opinion: EventWriter<OpinionEvent>,
+) {
+    let mut rng = thread_rng();
+
+    for mut crisis in &mut query_crises {
+        if crisis.days == 0 {
+            factions.adjust(crisis.reigning, 0, DISPUTED_LEGITIMACY);
+        }
+        crisis.days += 1;
+
+        if let Some(weakest) = factions.weakest(&crisis.claimants) {
+            if let Ok((name, personality)) = query_people.get(weakest) {
+                if rng.gen_bool(DAILY_NEGOTIATION_CHANCE * personality.loyalty) {
+                    crisis.claimants.retain(|claimant| *claimant != weakest);
+                    info!(
+                        "{} {} is persuaded to renounce their claim to the crown",
+                        name.first, name.last
+                    );
+                }
+            }
+        }
+
+        for plotter in crisis.claimants.clone() {
+            // murdered earlier today by another claimant
+            if !crisis.claimants.contains(&plotter) {
+                continue;
+            }
+            let Ok((name, personality)) = query_people.get(plotter) else {
+                continue;
+            };
+            let target = crisis
+                .claimants
+                .iter()
+                .copied()
+                .filter(|rival| *rival != plotter)
+                .max_by_key(|rival| factions.strength(*rival));
+            let Some((target, target_name)) = target.and_then(|target| {
+                query_people
+                    .get(target)
+                    .ok()
+                    .map(|(name, _)| (target, name))
+            }) else {
+                continue;
+            };
+            if !rng.gen_bool(DAILY_ASSASSINATION_CHANCE * personality.cruelty) {
+                continue;
+            }
+
+            if rng.gen_bool(ASSASSINATION_SUCCESS_CHANCE) {
+                warn!(
+                    "{} {} has their rival {} {} assassinated",
+                    name.first, name.last, target_name.first, target_name.last
+                );
+                ev_death.send(DeathEvent::new(target, "Assassinated"));
+                // nobody else gets to kill them too
+                crisis.claimants.retain(|claimant| *claimant != target);
+            } else {
+                warn!(
+                    "{} {}'s plot to murder {} {} is discovered",
+                    name.first, name.last, target_name.first, target_name.last
+                );
+                factions.adjust(plotter, DISCOVERED_PRESTIGE, DISCOVERED_PRESTIGE / 5);
+                ev_opinion.send(OpinionEvent::new(
+                    target,
+                    plotter,
+                    DISCOVERED_OPINION,
+                    "plotted to murder them",
+                ));
+            }
+        }
+
+        if !crisis.at_war && crisis.days >= DAYS_BEFORE_WAR && crisis.claimants.len() > 1 {
+            crisis.at_war = true;
+            warn!("Talks have failed and the succession crisis has become a civil war");
+        }
+    }
+}
+
+/// Claimants at war meet in battle, the stronger faction more likely to win. Enough victories
+/// crush a rival, who may be put to death if the victor is cruel.
+fn wage_civil_war(
+    mut factions: Factions,
+    mut query_crises: Query<&mut SuccessionCrisis>,
+    query_people: Query<(&Name, &Personality)>,
+    mut ev_death: EventWriter<DeathEvent>,
+    mut ev_peril: EventWriter<PerilEvent>,
+) {
+    let mut rng = thread_rng();
+
+    for mut crisis in &mut query_crises {
+        if !crisis.at_war || crisis.claimants.len() < 2 || !rng.gen_bool(DAILY_BATTLE_CHANCE) {
+            continue;
+        }
+
+        let mut sides = crisis.claimants.choose_multiple(&mut rng, 2).copied();
+        let (Some(first), Some(second)) = (sides.next(), sides.next()) else {
+            continue;
+        };
+        let first_strength = f64::from(factions.strength(first).max(1));
+        let second_strength = f64::from(factions.strength(second).max(1));
+        let (victor, loser) = if rng.gen_bool(first_strength / (first_strength + second_strength)) {
+            (first, second)
+        } else {
+            (second, first)
+        };
+        let (Ok((victor_name, personality)), Ok((loser_name, _))) =
+            (query_people.get(victor), query_people.get(loser))
+        else {
+            continue;
+        };
+
+        info!(
+            "The forces of {} {} defeat those of {} {} in battle",
+            victor_name.first, victor_name.last, loser_name.first, loser_name.last
+        );
+        factions.adjust(victor, VICTORY_PRESTIGE, VICTORY_LEGITIMACY);
+        factions.adjust(loser, DEFEAT_PRESTIGE, -VICTORY_LEGITIMACY);
+        for backer in factions.backers(loser) {
+            if rng.gen_bool(BATTLE_DEATH_CHANCE) {
+                ev_peril.send(PerilEvent::new(backer, "Fell in battle"));
+            }
+        }
+
+        let victories = crisis.victories.entry(victor).or_default();
+        *victories += 1;
+        if *victories < DECISIVE_VICTORIES {
+            continue;
+        }
+        *victories = 0;
+        crisis.claimants.retain(|claimant| *claimant != loser);
+        if rng.gen_bool(personality.cruelty) {
+            warn!(
+                "{} {} is captured and executed on the orders of {} {}",
+                loser_name.first, loser_name.last, victor_name.first, victor_name.last
+            );
+            ev_death.send(DeathEvent::new(loser, "Executed"));
+        } else {
+            info!(
+                "{} {} is crushed and gives up their claim",
+                loser_name.first, loser_name.last
+            );
+        }
+    }
+}
+
+/// Dead and exiled claimants drop out and a new monarch has a stake in the quarrel. Once a single
+/// claimant is left they take the throne, and the factions disband.
+fn settle_crises(
+    mut commands: Commands,
+    claimants: Claimants,
+    mut query_crises: Query<(Entity, &mut SuccessionCrisis)>,
+    query_monarchs: Query<(Entity, &Royalty), With<Alive>>,
+    query_backers: Query<(Entity, &BacksClaimant)>,
+    query_names: Query<&Name>,
+    mut ev_succession: EventWriter<SuccessionEvent>,
+) {
+    let monarch = query_monarchs
+        .iter()
+        .find(|(_, royalty)| royalty.title == Title::Monarch)
+        .map(|(monarch, _)| monarch);
+
+    let mut contending = Vec::new();
+    for (entity, mut crisis) in &mut query_crises {
+        crisis
+            .claimants
+            .retain(|claimant| claimants.can_claim(*claimant));
+        // a monarch who inherited the crown mid-crisis inherits the quarrel too
+        if let Some(monarch) = monarch.filter(|monarch| *monarch != crisis.reigning) {
+            crisis.reigning = monarch;
+            if !crisis.claimants.contains(&monarch) {
+                crisis.claimants.insert(0, monarch);
+            }
+        }
+        if crisis.claimants.len() > 1 {
+            contending.extend(crisis.claimants.iter().copied());
+            continue;
+        }
+
+        commands.entity(entity).despawn();
+        let Some(victor) = crisis.claimants.first().copied() else {
+            continue;
+        };
+        let Ok(name) = query_names.get(victor) else {
+            continue;
+        };
+        info!(
+            "The succession crisis begun {} is over after {} days, {} {} holds the crown",
+            crisis.started, crisis.days, name.first, name.last
+        );
+        if let Some(deposed) = monarch.filter(|monarch| *monarch != victor) {
+            claimants.crown(&mut commands, victor, deposed);
+            ev_succession.send(SuccessionEvent::contested(deposed, victor));
+        }
+    }
+
+    for (backer, backing) in &query_backers {
+        if !contending.contains(&backing.claimant) {
+            commands.entity(backer).remove::<BacksClaimant>();
+        }
+    }
+}
+
+pub struct CrisisPlugin;
+
+impl Plugin for CrisisPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                detect_disputes,
+                settle_crises,
+                rally_factions,
+                press_claims,
+                wage_civil_war,
+            )
+                .chain()
+                .run_if(in_state(RunState::Running)),
+        );
+    }
+}
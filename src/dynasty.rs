@@ -0,0 +1,231 @@
+use std::fmt;
+
+use bevy::{prelude::*, utils::HashMap};
+use rand::{
+    distributions::{Distribution, Standard},
+    prelude::*,
+};
+
+use crate::{
+    moon::MoonHouse,
+    people::{AssignedMoonHouse, Name, Parents, Person},
+    royalty::Royalty,
+    sex::Gender,
+    state::RunState,
+};
+
+/// Heraldic colours and metals, a metal goes on a colour and a colour on a metal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tincture {
+    Or,
+    Argent,
+    Gules,
+    Azure,
+    Vert,
+    Sable,
+    Purpure,
+}
+
+impl Tincture {
+    pub fn is_metal(self) -> bool {
+        matches!(self, Tincture::Or | Tincture::Argent)
+    }
+}
+
+impl Distribution<Tincture> for Standard {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Tincture {
+        use Tincture::{Argent, Azure, Gules, Or, Purpure, Sable, Vert};
+        match rng.gen_range(0..7) {
+            0 => Or,
+            1 => Argent,
+            2 => Gules,
+            3 => Azure,
+            4 => Vert,
+            5 => Sable,
+            _ => Purpure,
+        }
+    }
+}
+
+/// The beast or thing shown on a coat of arms
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Charge {
+    Lion,
+    Eagle,
+    Stag,
+    Tower,
+    Moon,
+    Sword,
+    Rose,
+}
+
+impl fmt::Display for Charge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let charge = match self {
+            Charge::Lion => "lion rampant",
+            Charge::Eagle => "eagle displayed",
+            Charge::Stag => "stag at gaze",
+            Charge::Tower => "tower",
+            Charge::Moon => "crescent moon",
+            Charge::Sword => "sword erect",
+            Charge::Rose => "rose",
+        };
+        write!(f, "{charge}")
+    }
+}
+
+impl Distribution<Charge> for Standard {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Charge {
+        use Charge::{Eagle, Lion, Moon, Rose, Stag, Sword, Tower};
+        match rng.gen_range(0..7) {
+            0 => Lion,
+            1 => Eagle,
+            2 => Stag,
+            3 => Tower,
+            4 => Moon,
+            5 => Sword,
+            _ => Rose,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct CoatOfArms {
+    pub field: Tincture,
+    pub charge: Charge,
+    pub tincture: Tincture,
+}
+
+impl CoatOfArms {
+    /// Random arms that follow the rule of tincture
+    pub fn random(rng: &mut ThreadRng) -> Self {
+        let field: Tincture = rng.gen();
+        let mut tincture: Tincture = rng.gen();
+        while tincture.is_metal() == field.is_metal() {
+            tincture = rng.gen();
+        }
+        CoatOfArms {
+            field,
+            charge: rng.gen(),
+            tincture,
+        }
+    }
+}
+
+/// Blazoned the heraldic way, e.g. "Azure, a lion rampant Or"
+impl fmt::Display for CoatOfArms {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}, a {} {:?}", self.field, self.charge, self.tincture)
+    }
+}
+
+/// A family line, named for the surname of whoever founded it
+#[derive(Component, Debug)]
+pub struct Dynasty {
+    pub name: String,
+    pub arms: CoatOfArms,
+    pub founder: Entity,
+    /// The founder's High House, whose ascendancy favours the dynasty
+    pub house: MoonHouse,
+}
+
+impl fmt::Display for Dynasty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "House {}", self.name)
+    }
+}
+
+/// Which dynasty a person belongs to. Dynasties find their members by querying for it.
+#[derive(Component, Debug)]
+pub struct InDynasty {
+    pub dynasty: Entity,
+}
+
+/// Which parent's dynasty a child belongs to: a royal parent's, else their father's, else either
+fn dynasty_from_parents(
+    parents: &Parents,
+    known: &HashMap<Entity, Entity>,
+    query_parents: &Query<(Option<&Royalty>, &Gender)>,
+) -> Option<Entity> {
+    parents
+        .iter()
+        .filter(|parent| known.contains_key(*parent))
+        .max_by_key(|parent| {
+            query_parents
+                .get(**parent)
+                .map_or((false, false), |(royalty, gender)| {
+                    (royalty.is_some(), *gender == Gender::Man)
+                })
+        })
+        .map(|parent| known[parent])
+}
+
+/// Put everyone in a dynasty. Children join a parent's, people without known parents found
+/// their own or join the one named for their surname.
+fn join_dynasties(
+    mut commands: Commands,
+    query: Query<(Entity, &Name, &Parents, &AssignedMoonHouse), (With<Person>, Without<InDynasty>)>,
+    query_members: Query<(Entity, &InDynasty)>,
+    query_dynasties: Query<(Entity, &Dynasty)>,
+    query_parents: Query<(Option<&Royalty>, &Gender)>,
+) {
+    let mut rng = thread_rng();
+    let mut known: HashMap<Entity, Entity> = query_members
+        .iter()
+        .map(|(person, member)| (person, member.dynasty))
+        .collect();
+    let mut by_name: HashMap<String, Entity> = query_dynasties
+        .iter()
+        .map(|(dynasty, details)| (details.name.clone(), dynasty))
+        .collect();
+
+    let mut pending: Vec<(Entity, &Name, &Parents, &AssignedMoonHouse)> = query.iter().collect();
+    // royals found their houses first, so the royal house is named for them
+    pending.sort_by_key(|(person, _, _, _)| {
+        !query_parents
+            .get(*person)
+            .is_ok_and(|(royalty, _)| royalty.is_some())
+    });
+    // parents have to be placed before their children, so keep going while anyone new is placed
+    loop {
+        let waiting = pending.len();
+        pending.retain(|(person, name, parents, assigned_house)| {
+            let unplaced_parent = parents
+                .iter()
+                .any(|parent| !known.contains_key(parent) && query.contains(*parent));
+            if unplaced_parent {
+                return true;
+            }
+
+            let dynasty =
+                dynasty_from_parents(parents, &known, &query_parents).unwrap_or_else(|| {
+                    *by_name.entry(name.last.clone()).or_insert_with(|| {
+                        info!("{} {} founds House {}", name.first, name.last, name.last);
+                        commands
+                            .spawn(Dynasty {
+                                name: name.last.clone(),
+                                arms: CoatOfArms::random(&mut rng),
+                                founder: *person,
+                                house: assigned_house.house.clone(),
+                            })
+                            .id()
+                    })
+                });
+            known.insert(*person, dynasty);
+            commands.entity(*person).insert(InDynasty { dynasty });
+            false
+        });
+        if pending.is_empty() || pending.len() == waiting {
+            break;
+        }
+    }
+}
+
+pub struct DynastyPlugin;
+
+impl Plugin for DynastyPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(PostStartup, join_dynasties)
+            .add_systems(Update, join_dynasties.run_if(in_state(RunState::Running)));
+    }
+}
@@ -0,0 +1,583 @@
+use std::fmt;
+
+use bevy::{ecs::system::SystemParam, prelude::*, utils::HashMap};
+use rand::prelude::*;
+
+use crate::{
+    calendar::{Calendar, Date},
+    crisis::{BacksClaimant, Factions, SuccessionCrisis},
+    life::{Alive, DeathEvent},
+    people::Name,
+    personality::Personality,
+    regency::{acting_ruler, Regent},
+    relationships::OpinionEvent,
+    royalty::{Legitimacy, Prestige, Royalty, Taxes, Title, MIN_TAX_RATE},
+    state::RunState,
+    succession::{Claimants, SuccessionEvent, SuccessionLaw, SuccessionRules},
+    titles::LandedTitle,
+};
+
+/// Chance each month that a noble with nothing to be content about joins a faction
+const MONTHLY_JOIN_CHANCE: f64 = 0.2;
+/// Chance each month that the most loyal member gives up on their faction
+const MONTHLY_LEAVE_CHANCE: f64 = 0.1;
+/// Members who think this well of the monarch leave their faction
+const CONTENT_OPINION: i32 = 25;
+/// Nobles more ambitious than this want a say in who rules
+const AMBITIOUS: f64 = 0.6;
+/// Strength each member adds to a faction, or to the crown if they stay loyal, on top of prestige
+const MEMBER_STRENGTH: i32 = 50;
+/// Strength the crown draws from each point of the monarch's legitimacy
+const LEGITIMACY_STRENGTH: i32 = 5;
+/// Months a faction gathers support before it makes demands
+const MONTHS_BEFORE_ULTIMATUM: u32 = 3;
+/// Share of the strength in the realm a faction needs before it makes demands
+const ULTIMATUM_ODDS: f64 = 0.3;
+/// Share of the strength in the realm a faction needs to rise in revolt when refused
+const REVOLT_ODDS: f64 = 0.4;
+const REFUSED_OPINION: i32 = -20;
+const ULTIMATUM_OPINION: i32 = -20;
+const REVOLT_LEGITIMACY: i32 = -20;
+const REVOLT_PRESTIGE: i32 = 50;
+const TREASON_PRESTIGE: i32 = -50;
+
+/// What a faction wants from the crown
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FactionGoal {
+    ChangeSuccessionLaw(SuccessionLaw),
+    InstallClaimant(Entity),
+    LowerTaxes,
+}
+
+impl fmt::Display for FactionGoal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FactionGoal::ChangeSuccessionLaw(law) => write!(f, "make the succession {law}"),
+            FactionGoal::InstallClaimant(_) => write!(f, "put their claimant on the throne"),
+            FactionGoal::LowerTaxes => write!(f, "lower taxes"),
+        }
+    }
+}
+
+/// Nobles banded together to force the crown's hand
+#[derive(Component, Debug)]
+pub struct Faction {
+    pub goal: FactionGoal,
+    pub leader: Entity,
+    pub founded: Date,
+    /// Months spent gathering support since the faction was founded or last made demands
+    pub months: u32,
+}
+
+/// A noble who has joined a faction
+#[derive(Component, Debug)]
+pub struct FactionMember {
+    pub faction: Entity,
+}
+
+/// A faction has grown strong enough to make demands of the monarch
+#[derive(Event, Debug)]
+pub struct UltimatumEvent {
+    faction: Entity,
+    odds: f64,
+}
+
+impl UltimatumEvent {
+    pub fn new(faction: Entity, odds: f64) -> Self {
+        UltimatumEvent { faction, odds }
+    }
+
+    pub fn faction(&self) -> Entity {
+        self.faction
+    }
+
+    /// The faction's share of the strength in the realm
+    pub fn odds(&self) -> f64 {
+        self.odds
+    }
+}
+
+/// A faction whose demands were refused has taken up arms
+#[derive(Event, Debug)]
+pub struct RevoltEvent {
+    faction: Entity,
+    odds: f64,
+}
+
+impl RevoltEvent {
+    pub fn new(faction: Entity, odds: f64) -> Self {
+        RevoltEvent { faction, odds }
+    }
+
+    pub fn faction(&self) -> Entity {
+        self.faction
+    }
+
+    pub fn odds(&self) -> f64 {
+        self.odds
+    }
+}
+
+/// The monarch, the nobles of the realm, and what the crown can give up to a faction
+#[derive(SystemParam)]
+pub struct Crown<'w, 's> {
+    claimants: Claimants<'w, 's>,
+    rules: ResMut<'w, SuccessionRules>,
+    taxes: ResMut<'w, Taxes>,
+    query_calendar: Query<'w, 's, &'static Calendar>,
+    query_royals: Query<'w, 's, (Entity, &'static Royalty), With<Alive>>,
+    query_titles: Query<'w, 's, &'static LandedTitle>,
+    query_crises: Query<'w, 's, (), With<SuccessionCrisis>>,
+    query_regents: Query<'w, 's, (Entity, &'static Regent), With<Alive>>,
+    ev_succession: EventWriter<'w, SuccessionEvent>,
+}
+
+impl<'w, 's> Crown<'w, 's> {
+    pub fn claimants(&self) -> &Claimants<'w, 's> {
+        &self.claimants
+    }
+
+    pub fn date(&self) -> Date {
+        self.query_calendar.single().date()
+    }
+
+    pub fn monarch(&self) -> Option<Entity> {
+        self.query_royals
+            .iter()
+            .find(|(_, royalty)| royalty.title == Title::Monarch)
+            .map(|(monarch, _)| monarch)
+    }
+
+    /// Who answers for the crown: the monarch, or their regent while they're too young
+    pub fn ruler(&self) -> Option<Entity> {
+        self.monarch()
+            .map(|monarch| acting_ruler(monarch, &self.query_regents))
+    }
+
+    /// The living royals and title holders, other than the monarch
+    pub fn nobles(&self) -> Vec<Entity> {
+        let monarch = self.monarch();
+        let mut nobles: Vec<Entity> = self
+            .query_royals
+            .iter()
+            .map(|(royal, _)| royal)
+            .chain(self.query_titles.iter().filter_map(|title| title.holder))
+            .filter(|noble| Some(*noble) != monarch)
+            .collect();
+        nobles.sort();
+        nobles.dedup();
+        nobles
+    }
+
+    pub fn in_crisis(&self) -> bool {
+        !self.query_crises.is_empty()
+    }
+
+    /// Whether there's nothing left to fight for
+    pub fn is_met(&self, goal: FactionGoal) -> bool {
+        match goal {
+            FactionGoal::ChangeSuccessionLaw(law) => self.rules.law == law,
+            FactionGoal::InstallClaimant(claimant) => {
+                !self.claimants.can_claim(claimant) || self.monarch() == Some(claimant)
+            }
+            FactionGoal::LowerTaxes => self.taxes.rate <= MIN_TAX_RATE,
+        }
+    }
+
+    /// What a faction wants: a claimant they'd rather see on the throne, a law that would put
+    /// them closer to it, or failing that lighter taxes
+    pub fn goal_for(&self, noble: Entity, monarch: Entity, ambition: f64) -> FactionGoal {
+        if ambition <= AMBITIOUS {
+            return FactionGoal::LowerTaxes;
+        }
+
+        let line = self.claimants.ranked(monarch, self.rules.law);
+        let favourite = line
+            .iter()
+            .copied()
+            .filter(|person| self.claimants.is_adult(*person))
+            .max_by_key(|person| {
+                if *person == noble {
+                    i32::MAX
+                } else {
+                    self.claimants.opinion(noble, *person)
+                }
+            });
+        if let Some(favourite) = favourite {
+            if favourite == noble
+                || self.claimants.opinion(noble, favourite) > self.claimants.opinion(noble, monarch)
+            {
+                return FactionGoal::InstallClaimant(favourite);
+            }
+        }
+
+        let place = |law: SuccessionLaw| {
+            self.claimants
+                .ranked(monarch, law)
+                .iter()
+                .position(|person| *person == noble)
+        };
+        let current = place(self.rules.law);
+        SuccessionLaw::ALL
+            .into_iter()
+            .filter_map(|law| place(law).map(|position| (law, position)))
+            .filter(|(_, position)| current.is_none_or(|current| *position < current))
+            .min_by_key(|(_, position)| *position)
+            .map_or(FactionGoal::LowerTaxes, |(law, _)| {
+                FactionGoal::ChangeSuccessionLaw(law)
+            })
+    }
+
+    /// Give the faction what it wants. A claimant is handed the crown by the monarch.
+    pub fn concede(&mut self, commands: &mut Commands, monarch: Entity, goal: FactionGoal) {
+        match goal {
+            FactionGoal::ChangeSuccessionLaw(law) => {
+                self.rules.law = law;
+                info!("The succession will now follow {}", law);
+            }
+            FactionGoal::InstallClaimant(claimant) => {
+                self.claimants.crown(commands, claimant, monarch);
+                self.ev_succession
+                    .send(SuccessionEvent::contested(monarch, claimant));
+            }
+            FactionGoal::LowerTaxes => {
+                self.taxes.lower();
+                info!("Taxes are lowered to {}%", self.taxes.rate);
+            }
+        }
+    }
+}
+
+/// Break up a faction, its members go back to their lands
+fn disband(commands: &mut Commands, faction: Entity, members: impl Iterator<Item = Entity>) {
+    commands.entity(faction).despawn();
+    for member in members {
+        commands.entity(member).remove::<FactionMember>();
+    }
+}
+
+/// Factions break up once they have nothing to fight for or no one left to fight. The monarch
+/// can't be part of a faction against themselves.
+fn dissolve_factions(
+    mut commands: Commands,
+    crown: Crown,
+    query_factions: Query<(Entity, &Faction)>,
+    query_members: Query<(Entity, &FactionMember, Has<Alive>)>,
+) {
+    let monarch = crown.monarch();
+    for (faction, details) in &query_factions {
+        let members: Vec<(Entity, bool)> = query_members
+            .iter()
+            .filter(|(_, member, _)| member.faction == faction)
+            .map(|(member, _, alive)| (member, alive && Some(member) != monarch))
+            .collect();
+        if crown.is_met(details.goal) || !members.iter().any(|(_, active)| *active) {
+            debug!("A faction that wanted to {} has disbanded", details.goal);
+            disband(
+                &mut commands,
+                faction,
+                members.into_iter().map(|(member, _)| member),
+            );
+        } else {
+            for (member, _) in members.iter().filter(|(_, active)| !*active) {
+                commands.entity(*member).remove::<FactionMember>();
+            }
+        }
+    }
+}
+
+/// At the start of each month the discontented join factions, founding one if none shares their
+/// goal, and those won back by the monarch leave
+fn gather_factions(
+    mut commands: Commands,
+    crown: Crown,
+    mut query_factions: Query<(Entity, &mut Faction)>,
+    query_nobles: Query<(&Name, &Personality, Option<&FactionMember>), With<Alive>>,
+    query_names: Query<&Name>,
+) {
+    let date = crown.date();
+    let Some(monarch) = crown.monarch().filter(|_| date.month_day == 1) else {
+        return;
+    };
+    let mut rng = thread_rng();
+    let burden = f64::from(crown.taxes.burden()) / 100.0;
+
+    for (_, mut faction) in &mut query_factions {
+        faction.months += 1;
+    }
+    let mut founded: HashMap<FactionGoal, Entity> = query_factions
+        .iter()
+        .map(|(faction, details)| (details.goal, faction))
+        .collect();
+
+    for noble in crown.nobles() {
+        let Ok((name, personality, membership)) = query_nobles.get(noble) else {
+            continue;
+        };
+        let opinion = crown.claimants().opinion(noble, monarch);
+
+        if membership.is_some() {
+            if opinion >= CONTENT_OPINION
+                || rng.gen_bool(MONTHLY_LEAVE_CHANCE * personality.loyalty)
+            {
+                commands.entity(noble).remove::<FactionMember>();
+                debug!("{} {} has left their faction", name.first, name.last);
+            }
+            continue;
+        }
+
+        let discontent =
+            (1.0 - personality.loyalty - f64::from(opinion) / 100.0 + burden).clamp(0.0, 1.0);
+        if !rng.gen_bool(MONTHLY_JOIN_CHANCE * discontent) {
+            continue;
+        }
+
+        let goal = crown.goal_for(noble, monarch, personality.ambition);
+        let faction = *founded.entry(goal).or_insert_with(|| {
+            let claimant = match goal {
+                FactionGoal::InstallClaimant(claimant) => query_names.get(claimant).map_or_else(
+                    |_| String::new(),
+                    |claimant| format!(", {} {}", claimant.first, claimant.last),
+                ),
+                _ => String::new(),
+            };
+            info!(
+                "{} {} founds a faction to {}{}",
+                name.first, name.last, goal, claimant
+            );
+            commands
+                .spawn(Faction {
+                    goal,
+                    leader: noble,
+                    founded: date,
+                    months: 0,
+                })
+                .id()
+        });
+        commands.entity(noble).insert(FactionMember { faction });
+        debug!("{} {} joins a faction to {}", name.first, name.last, goal);
+    }
+}
+
+/// A faction that has gathered enough strength against the crown makes its demands
+fn issue_ultimatums(
+    crown: Crown,
+    mut query_factions: Query<(Entity, &mut Faction)>,
+    query_people: Query<(&Prestige, Option<&Legitimacy>, Option<&FactionMember>), With<Alive>>,
+    mut ev_ultimatum: EventWriter<UltimatumEvent>,
+) {
+    let Some(monarch) = crown.monarch() else {
+        return;
+    };
+    let weight = |noble: Entity| {
+        query_people
+            .get(noble)
+            .map_or(0, |(prestige, ..)| prestige.score + MEMBER_STRENGTH)
+    };
+    let crown_strength = query_people
+        .get(monarch)
+        .map_or(0, |(prestige, legitimacy, _)| {
+            prestige.score
+                + legitimacy.map_or(0, |legitimacy| legitimacy.score) * LEGITIMACY_STRENGTH
+        });
+    let nobles = crown.nobles();
+    let loyalists: i32 = nobles
+        .iter()
+        .filter(|noble| {
+            query_people
+                .get(**noble)
+                .is_ok_and(|(.., member)| member.is_none())
+        })
+        .map(|noble| weight(*noble))
+        .sum();
+
+    for (faction, mut details) in &mut query_factions {
+        if details.months < MONTHS_BEFORE_ULTIMATUM {
+            continue;
+        }
+        let strength: i32 = nobles
+            .iter()
+            .filter(|noble| {
+                query_people.get(**noble).is_ok_and(|(.., member)| {
+                    member.is_some_and(|member| member.faction == faction)
+                })
+            })
+            .map(|noble| weight(*noble))
+            .sum();
+        let odds = f64::from(strength) / f64::from((strength + crown_strength + loyalists).max(1));
+        if odds >= ULTIMATUM_ODDS {
+            details.months = 0;
+            ev_ultimatum.send(UltimatumEvent::new(faction, odds));
+        }
+    }
+}
+
+/// The crown gives in to the faction's demands, the more likely the stronger it is and the less
+/// ambitious whoever rules is. A refused faction revolts if it's strong enough, or backs down.
+fn answer_ultimatums(
+    mut commands: Commands,
+    mut crown: Crown,
+    mut ev_ultimatum: EventReader<UltimatumEvent>,
+    mut ev_revolt: EventWriter<RevoltEvent>,
+    mut ev_opinion: EventWriter<OpinionEvent>,
+    query_factions: Query<&Faction>,
+    query_people: Query<(Entity, &Name, &Personality, Option<&FactionMember>)>,
+) {
+    let mut rng = thread_rng();
+
+    for event in ev_ultimatum.read() {
+        let (Some(monarch), Some(ruler)) = (crown.monarch(), crown.ruler()) else {
+            continue;
+        };
+        let (Ok(faction), Ok((_, name, personality, _))) =
+            (query_factions.get(event.faction()), query_people.get(ruler))
+        else {
+            continue;
+        };
+        let members: Vec<Entity> = query_people
+            .iter()
+            .filter(|(.., member)| member.is_some_and(|member| member.faction == event.faction()))
+            .map(|(member, ..)| member)
+            .collect();
+        warn!(
+            "A faction {} strong, gathering since {}, demands that {} {} {}",
+            members.len(),
+            faction.founded,
+            name.first,
+            name.last,
+            faction.goal
+        );
+
+        let give_in = (event.odds() * 2.0 * (1.0 - personality.ambition)).clamp(0.0, 1.0);
+        if rng.gen_bool(give_in) {
+            info!(
+                "{} {} gives in to the faction's demands",
+                name.first, name.last
+            );
+            crown.concede(&mut commands, monarch, faction.goal);
+            disband(&mut commands, event.faction(), members.into_iter());
+            continue;
+        }
+
+        for member in &members {
+            ev_opinion.send(OpinionEvent::new(
+                *member,
+                ruler,
+                REFUSED_OPINION,
+                "refused their demands",
+            ));
+            ev_opinion.send(OpinionEvent::new(
+                ruler,
+                *member,
+                ULTIMATUM_OPINION,
+                "made demands of them",
+            ));
+        }
+        if event.odds() >= REVOLT_ODDS {
+            ev_revolt.send(RevoltEvent::new(event.faction(), event.odds()));
+        } else {
+            info!(
+                "{} {} refuses the faction's demands and they bide their time",
+                name.first, name.last
+            );
+        }
+    }
+}
+
+/// A faction backing a claimant plunges the realm into a succession crisis. Any other revolt is
+/// decided in a single battle: the victors get their way, the defeated leader risks the block.
+fn put_down_revolts(
+    mut commands: Commands,
+    mut crown: Crown,
+    mut factions: Factions,
+    mut ev_revolt: EventReader<RevoltEvent>,
+    mut ev_death: EventWriter<DeathEvent>,
+    query_factions: Query<&Faction>,
+    query_people: Query<(Entity, &Name, &Personality, Option<&FactionMember>)>,
+) {
+    let mut rng = thread_rng();
+    let mut in_crisis = crown.in_crisis();
+
+    for event in ev_revolt.read() {
+        let Some(monarch) = crown.monarch() else {
+            continue;
+        };
+        let Ok(faction) = query_factions.get(event.faction()) else {
+            continue;
+        };
+        let (Ok((_, name, personality, _)), Ok((_, leader_name, ..))) =
+            (query_people.get(monarch), query_people.get(faction.leader))
+        else {
+            continue;
+        };
+        let members: Vec<Entity> = query_people
+            .iter()
+            .filter(|(.., member)| member.is_some_and(|member| member.faction == event.faction()))
+            .map(|(member, ..)| member)
+            .collect();
+        warn!(
+            "{} {} leads a revolt against {} {}",
+            leader_name.first, leader_name.last, name.first, name.last
+        );
+
+        if let FactionGoal::InstallClaimant(claimant) = faction.goal {
+            if !in_crisis {
+                in_crisis = true;
+                for member in &members {
+                    commands.entity(*member).insert(BacksClaimant { claimant });
+                }
+                commands.spawn(SuccessionCrisis {
+                    reigning: monarch,
+                    claimants: vec![monarch, claimant],
+                    started: crown.date(),
+                    days: 0,
+                    at_war: true,
+                    victories: HashMap::new(),
+                });
+                disband(&mut commands, event.faction(), members.into_iter());
+                continue;
+            }
+        }
+
+        if rng.gen_bool(event.odds()) {
+            info!("The rebels defeat the crown's forces and get their way");
+            factions.adjust(monarch, 0, REVOLT_LEGITIMACY);
+            factions.adjust(faction.leader, REVOLT_PRESTIGE, 0);
+            crown.concede(&mut commands, monarch, faction.goal);
+        } else {
+            info!("The crown's forces crush the revolt");
+            for member in &members {
+                factions.adjust(*member, TREASON_PRESTIGE, 0);
+            }
+            if rng.gen_bool(personality.cruelty) {
+                warn!(
+                    "{} {} is executed for treason",
+                    leader_name.first, leader_name.last
+                );
+                ev_death.send(DeathEvent::new(faction.leader, "Executed for treason"));
+            }
+        }
+        disband(&mut commands, event.faction(), members.into_iter());
+    }
+}
+
+pub struct FactionPlugin;
+
+impl Plugin for FactionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<UltimatumEvent>()
+            .add_event::<RevoltEvent>()
+            .add_systems(
+                Update,
+                (
+                    dissolve_factions,
+                    gather_factions,
+                    issue_ultimatums,
+                    answer_ultimatums,
+                    put_down_revolts,
+                )
+                    .chain()
+                    .run_if(in_state(RunState::Running)),
+            );
+    }
+}
@@ -0,0 +1,289 @@
+use bevy::{
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+
+use crate::{
+    people::{rebuild_children, Children, Parents, Twins},
+    state::RunState,
+};
+
+/// Something wrong with the family tree, e.g. a parent that doesn't list their child
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FamilyViolation {
+    /// `child` lists `parent` but `parent` doesn't list them as a child
+    MissingChild { parent: Entity, child: Entity },
+    /// `parent` lists `child` but `child` doesn't list them as a parent
+    StaleChild { parent: Entity, child: Entity },
+    /// `person` lists `twin` as a twin but they don't share a parent
+    TwinNotSibling { person: Entity, twin: Entity },
+    /// `person` links to `relative` but there's no such person
+    Dangling { person: Entity, relative: Entity },
+}
+
+/// Whether the family check should fix what it finds or just complain about it
+#[derive(Resource, Debug)]
+pub struct FamilyCheck {
+    pub repair: bool,
+}
+
+impl Default for FamilyCheck {
+    /// Only report, so bugs in the hooks stay visible
+    fn default() -> Self {
+        FamilyCheck { repair: false }
+    }
+}
+
+/// Everyone's family links, copied out so the check doesn't have to juggle borrows
+struct Family {
+    parents: HashSet<Entity>,
+    children: HashSet<Entity>,
+    twins: HashSet<Entity>,
+}
+
+/// Check that every family link is mirrored on the other side, e.g. if A is B's parent then B is A's child.
+/// Parenthood hooks should make this impossible, so anything found here is a bug.
+pub fn check_family(world: &mut World) -> Vec<FamilyViolation> {
+    let families: HashMap<Entity, Family> = world
+        .query::<(Entity, &Parents, &Children, Option<&Twins>)>()
+        .iter(world)
+        .map(|(person, parents, children, twins)| {
+            let family = Family {
+                parents: parents.iter().copied().collect(),
+                children: children.iter().copied().collect(),
+                twins: twins
+                    .map(|twins| twins.zygosity.keys().copied().collect())
+                    .unwrap_or_default(),
+            };
+            (person, family)
+        })
+        .collect();
+
+    let mut violations = Vec::new();
+    for (person, family) in &families {
+        let links = family
+            .parents
+            .iter()
+            .chain(&family.children)
+            .chain(&family.twins);
+        for relative in links {
+            if !families.contains_key(relative) {
+                violations.push(FamilyViolation::Dangling {
+                    person: *person,
+                    relative: *relative,
+                });
+            }
+        }
+
+        for parent in &family.parents {
+            if families
+                .get(parent)
+                .is_some_and(|parent_family| !parent_family.children.contains(person))
+            {
+                violations.push(FamilyViolation::MissingChild {
+                    parent: *parent,
+                    child: *person,
+                });
+            }
+        }
+
+        for child in &family.children {
+            if families
+                .get(child)
+                .is_some_and(|child_family| !child_family.parents.contains(person))
+            {
+                violations.push(FamilyViolation::StaleChild {
+                    parent: *person,
+                    child: *child,
+                });
+            }
+        }
+
+        for twin in &family.twins {
+            if families
+                .get(twin)
+                .is_some_and(|twin_family| twin_family.parents.is_disjoint(&family.parents))
+            {
+                violations.push(FamilyViolation::TwinNotSibling {
+                    person: *person,
+                    twin: *twin,
+                });
+            }
+        }
+    }
+
+    violations
+}
+
+/// Fix the violations, trusting each child's `Parents` over anything else
+pub fn repair_family(world: &mut World, violations: &[FamilyViolation]) {
+    for violation in violations {
+        match *violation {
+            FamilyViolation::Dangling { person, relative } => {
+                if let Some(parents) = world.get::<Parents>(person) {
+                    let parents = parents
+                        .iter()
+                        .copied()
+                        .filter(|parent| *parent != relative)
+                        .collect();
+                    world.entity_mut(person).insert(Parents::new(parents));
+                }
+                if let Some(mut twins) = world.get_mut::<Twins>(person) {
+                    twins.zygosity.remove(&relative);
+                }
+            }
+            FamilyViolation::TwinNotSibling { person, twin } => {
+                if let Some(mut twins) = world.get_mut::<Twins>(person) {
+                    twins.zygosity.remove(&twin);
+                }
+            }
+            FamilyViolation::MissingChild { .. } | FamilyViolation::StaleChild { .. } => {}
+        }
+    }
+
+    rebuild_children(world);
+}
+
+/// Report anything wrong with the family tree, and fix it if configured to
+fn validate_family(world: &mut World) {
+    let violations = check_family(world);
+    if violations.is_empty() {
+        return;
+    }
+
+    for violation in &violations {
+        warn!("Family tree is inconsistent: {:?}", violation);
+    }
+    if world.resource::<FamilyCheck>().repair {
+        repair_family(world, &violations);
+        info!("Repaired {} family tree problems", violations.len());
+    }
+}
+
+/// Checks the family tree after every day, only in debug builds as it's slow
+pub struct FamilyCheckPlugin;
+
+impl Plugin for FamilyCheckPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<FamilyCheck>();
+        #[cfg(debug_assertions)]
+        app.add_systems(
+            PostUpdate,
+            validate_family.run_if(in_state(RunState::Running)),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::people::Zygosity;
+
+    fn spawn_person(world: &mut World, parents: &[Entity]) -> Entity {
+        let parents = Parents::new(parents.iter().copied().collect());
+        world.spawn((parents, Children::default())).id()
+    }
+
+    fn make_twins(world: &mut World, first: Entity, second: Entity) {
+        for (person, twin) in [(first, second), (second, first)] {
+            world.entity_mut(person).insert(Twins {
+                zygosity: HashMap::from([(twin, Zygosity::Fraternal)]),
+            });
+        }
+    }
+
+    fn check_and_repair(world: &mut World) -> Vec<FamilyViolation> {
+        world.flush();
+        let violations = check_family(world);
+        repair_family(world, &violations);
+        assert_eq!(check_family(world), Vec::new());
+        violations
+    }
+
+    #[test]
+    fn hooks_keep_the_tree_consistent() {
+        let mut world = World::new();
+        let mother = spawn_person(&mut world, &[]);
+        let father = spawn_person(&mut world, &[]);
+        let first = spawn_person(&mut world, &[mother, father]);
+        let second = spawn_person(&mut world, &[mother, father]);
+        make_twins(&mut world, first, second);
+
+        assert_eq!(check_and_repair(&mut world), Vec::new());
+        assert!(world.get::<Children>(mother).unwrap().contains(&first));
+    }
+
+    #[test]
+    fn repairs_a_parent_missing_their_child() {
+        let mut world = World::new();
+        let mother = spawn_person(&mut world, &[]);
+        let child = spawn_person(&mut world, &[mother]);
+        world.entity_mut(mother).insert(Children::default());
+
+        let violations = check_and_repair(&mut world);
+        assert_eq!(
+            violations,
+            vec![FamilyViolation::MissingChild {
+                parent: mother,
+                child
+            }]
+        );
+        assert!(world.get::<Children>(mother).unwrap().contains(&child));
+    }
+
+    #[test]
+    fn repairs_a_parent_claiming_someone_elses_child() {
+        let mut world = World::new();
+        let mother = spawn_person(&mut world, &[]);
+        let stranger = spawn_person(&mut world, &[]);
+        let child = spawn_person(&mut world, &[mother]);
+        // the mother's children move to the stranger, and the child forgets the mother
+        let children = world.entity_mut(mother).take::<Children>().unwrap();
+        world.entity_mut(stranger).insert(children);
+
+        let violations = check_and_repair(&mut world);
+        assert_eq!(
+            violations,
+            vec![FamilyViolation::StaleChild {
+                parent: stranger,
+                child
+            }]
+        );
+        assert!(world.get::<Children>(stranger).unwrap().is_empty());
+    }
+
+    #[test]
+    fn repairs_links_to_people_who_dont_exist() {
+        let mut world = World::new();
+        let ghost = Entity::from_raw(9999);
+        let orphan = spawn_person(&mut world, &[ghost]);
+
+        let violations = check_and_repair(&mut world);
+        assert_eq!(
+            violations,
+            vec![FamilyViolation::Dangling {
+                person: orphan,
+                relative: ghost
+            }]
+        );
+        assert!(world.get::<Parents>(orphan).unwrap().is_empty());
+    }
+
+    #[test]
+    fn repairs_twins_who_arent_siblings() {
+        let mut world = World::new();
+        let mother = spawn_person(&mut world, &[]);
+        let child = spawn_person(&mut world, &[mother]);
+        let stranger = spawn_person(&mut world, &[]);
+        make_twins(&mut world, child, stranger);
+
+        let violations = check_and_repair(&mut world);
+        assert_eq!(violations.len(), 2);
+        assert!(violations.contains(&FamilyViolation::TwinNotSibling {
+            person: child,
+            twin: stranger
+        }));
+        assert!(world.get::<Twins>(child).unwrap().zygosity.is_empty());
+        assert!(world.get::<Twins>(stranger).unwrap().zygosity.is_empty());
+    }
+}
@@ -0,0 +1,60 @@
+use bevy::{prelude::*, utils::HashSet};
+
+use crate::{
+    life::{distinct_deaths, Alive, DeathEvent},
+    people::Name,
+    state::RunState,
+};
+
+/// A group of people living under the same roof
+#[derive(Component, Debug)]
+pub struct Household;
+
+/// Which household a person lives in. This is the only place membership is stored,
+/// households find their members by querying for it.
+#[derive(Component, Debug)]
+pub struct InHousehold {
+    pub household: Entity,
+}
+
+fn handle_death_household(
+    mut commands: Commands,
+    mut ev_death: EventReader<DeathEvent>,
+    query: Query<&Name, With<InHousehold>>,
+) {
+    for event in distinct_deaths(&mut ev_death) {
+        // the dead don't live anywhere
+        if let Ok(name) = query.get(event.dying()) {
+            debug!("{} {} left their household", name.first, name.last);
+            commands.entity(event.dying()).remove::<InHousehold>();
+        }
+    }
+}
+
+fn cleanup_empty_households(
+    mut commands: Commands,
+    query_households: Query<Entity, With<Household>>,
+    query_members: Query<&InHousehold, With<Alive>>,
+) {
+    let occupied: HashSet<Entity> = query_members.iter().map(|m| m.household).collect();
+
+    for household in &query_households {
+        if !occupied.contains(&household) {
+            debug!("Household {} is empty, removing it", household);
+            commands.entity(household).despawn();
+        }
+    }
+}
+
+pub struct HouseholdPlugin;
+
+impl Plugin for HouseholdPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (handle_death_household, cleanup_empty_households)
+                .chain()
+                .run_if(in_state(RunState::Running)),
+        );
+    }
+}
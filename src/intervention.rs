@@ -0,0 +1,421 @@
+use std::fmt;
+
+use bevy::prelude::*;
+use rand::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    calendar::Calendar,
+    crime::Exiled,
+    life::{Alive, PerilEvent, TouchedByHouse},
+    memories::{Memories, MemoryKind},
+    moon::{Moon, MoonHouse},
+    people::Name,
+    religion::{Devotion, Heretic},
+    royalty::Prestige,
+    scheme::{Scheme, SchemeEvent, SchemeOutcome},
+    state::RunState,
+};
+
+/// Where to read the intervention table from, the built-in one is used if it's missing
+const INTERVENTIONS_PATH: &str = "assets/interventions.ron";
+/// Total fervour among a house's faithful that doubles the chance of it stirring
+const FERVOUR_PER_FAVOUR: f64 = 10.0;
+/// Heretics draw a house's wrath more than anyone
+const HERETIC_WRATH: f64 = 2.0;
+const VISION_FERVOUR: f64 = 0.2;
+/// Chance a beast attack leaves someone at death's door rather than just scarred
+const MAULING_CHANCE: f64 = 0.3;
+const BLAZE_PRESTIGE: i32 = -20;
+const BOON_WEIGHT: f64 = 0.5;
+const CURSE_WEIGHT: f64 = -0.5;
+
+/// Something a house can do to someone
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum InterventionKind {
+    /// A dream that may reveal a plot against them
+    Vision,
+    BeastAttack,
+    /// Fire takes their barn and their standing with it
+    Blaze,
+    /// Wards off House Death from those it has a claim on
+    Healing,
+}
+
+impl fmt::Display for InterventionKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self {
+            InterventionKind::Vision => "a vision",
+            InterventionKind::BeastAttack => "a beast attack",
+            InterventionKind::Blaze => "a blaze",
+            InterventionKind::Healing => "a healing",
+        };
+        write!(f, "{kind}")
+    }
+}
+
+/// Who a house singles out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum Target {
+    /// Those who worship it, the more fervently the likelier
+    Devoted,
+    /// Those who don't, heretics above all
+    Faithless,
+}
+
+/// One row of the intervention table
+#[derive(Debug, Clone, Deserialize)]
+pub struct Intervention {
+    pub house: MoonHouse,
+    pub kind: InterventionKind,
+    pub target: Target,
+    /// Chance each day the house is ascendant, before its faithful's fervour is counted
+    pub chance: f64,
+}
+
+/// What each house may do while ascendant, read from `assets/interventions.ron`
+#[derive(Resource, Debug, Deserialize)]
+pub struct Interventions {
+    pub table: Vec<Intervention>,
+}
+
+impl Interventions {
+    /// Read the table from a RON file
+    pub fn load(path: &str) -> Result<Self, String> {
+        let data = std::fs::read_to_string(path).map_err(|error| error.to_string())?;
+        ron::from_str(&data).map_err(|error| error.to_string())
+    }
+}
+
+impl Default for Interventions {
+    /// The table the game ships with
+    fn default() -> Self {
+        ron::from_str(include_str!("../assets/interventions.ron"))
+            .expect("the built-in intervention table is valid")
+    }
+}
+
+/// A house has reached into someone's life
+#[derive(Event, Debug)]
+pub struct InterventionEvent {
+    house: MoonHouse,
+    kind: InterventionKind,
+    target: Entity,
+}
+
+impl InterventionEvent {
+    pub fn new(house: MoonHouse, kind: InterventionKind, target: Entity) -> Self {
+        InterventionEvent {
+            house,
+            kind,
+            target,
+        }
+    }
+
+    pub fn house(&self) -> &MoonHouse {
+        &self.house
+    }
+
+    pub fn kind(&self) -> InterventionKind {
+        self.kind
+    }
+
+    pub fn target(&self) -> Entity {
+        self.target
+    }
+}
+
+/// What came of an intervention, for the story
+#[derive(Event, Debug)]
+pub struct VisitationEvent {
+    house: MoonHouse,
+    kind: InterventionKind,
+    target: Entity,
+    /// What the house did, e.g. "burns down the barn of"
+    deed: String,
+    boon: bool,
+}
+
+impl VisitationEvent {
+    pub fn new(
+        house: MoonHouse,
+        kind: InterventionKind,
+        target: Entity,
+        deed: &str,
+        boon: bool,
+    ) -> Self {
+        VisitationEvent {
+            house,
+            kind,
+            target,
+            deed: deed.into(),
+            boon,
+        }
+    }
+
+    pub fn house(&self) -> &MoonHouse {
+        &self.house
+    }
+
+    pub fn kind(&self) -> InterventionKind {
+        self.kind
+    }
+
+    pub fn target(&self) -> Entity {
+        self.target
+    }
+
+    pub fn deed(&self) -> &str {
+        &self.deed
+    }
+
+    pub fn is_boon(&self) -> bool {
+        self.boon
+    }
+}
+
+/// The ascendant house stirs, more often the more fervently it's worshipped, and singles someone
+/// out for each row of its table that comes up
+fn stir_the_houses(
+    interventions: Res<Interventions>,
+    query_moon: Query<&Moon>,
+    query_people: Query<(Entity, Option<&Devotion>, Has<Heretic>), (With<Alive>, Without<Exiled>)>,
+    mut ev_intervention: EventWriter<InterventionEvent>,
+) {
+    let moon = query_moon.single();
+    let mut rng = thread_rng();
+
+    let devotion_to = |devotion: Option<&Devotion>| {
+        devotion
+            .filter(|devotion| devotion.house == moon.house)
+            .map_or(0.0, |devotion| devotion.fervour)
+    };
+    let fervour: f64 = query_people
+        .iter()
+        .filter(|(_, _, heretic)| !heretic)
+        .map(|(_, devotion, _)| devotion_to(devotion))
+        .sum();
+
+    for row in &interventions.table {
+        if row.house != moon.house
+            || !rng.gen_bool((row.chance * (1.0 + fervour / FERVOUR_PER_FAVOUR)).min(1.0))
+        {
+            continue;
+        }
+
+        let people: Vec<(Entity, Option<&Devotion>, bool)> = query_people.iter().collect();
+        let chosen = people.choose_weighted(&mut rng, |(_, devotion, heretic)| {
+            match (row.target, heretic) {
+                (Target::Devoted, true) => 0.0,
+                (Target::Devoted, false) => devotion_to(*devotion),
+                (Target::Faithless, true) => HERETIC_WRATH,
+                (Target::Faithless, false) => 1.0 - devotion_to(*devotion),
+            }
+        });
+        if let Ok((target, _, _)) = chosen {
+            ev_intervention.send(InterventionEvent::new(row.house.clone(), row.kind, *target));
+        }
+    }
+}
+
+/// Dreams warn people of plots against them, or else deepen their faith
+fn send_visions(
+    mut commands: Commands,
+    mut ev_intervention: EventReader<InterventionEvent>,
+    mut ev_scheme: EventWriter<SchemeEvent>,
+    mut ev_visitation: EventWriter<VisitationEvent>,
+    query_schemes: Query<(Entity, &Scheme)>,
+    mut query_devotion: Query<&mut Devotion>,
+) {
+    for event in ev_intervention.read() {
+        if event.kind() != InterventionKind::Vision {
+            continue;
+        }
+        let target = event.target();
+
+        let plot = query_schemes
+            .iter()
+            .find(|(_, scheme)| scheme.target == target);
+        let deed = if let Some((entity, scheme)) = plot {
+            commands.entity(entity).despawn();
+            ev_scheme.send(SchemeEvent::new(
+                scheme.kind,
+                scheme.plotter,
+                target,
+                SchemeOutcome::Exposed,
+            ));
+            "reveals a plot in a dream to"
+        } else {
+            if let Ok(mut devotion) = query_devotion.get_mut(target) {
+                devotion.fervour = (devotion.fervour + VISION_FERVOUR).min(1.0);
+            }
+            "sends a vision to"
+        };
+        ev_visitation.send(VisitationEvent::new(
+            event.house().clone(),
+            event.kind(),
+            target,
+            deed,
+            true,
+        ));
+    }
+}
+
+/// Beasts come out of the wild, and some of those they maul are left at death's door
+fn loose_beasts(
+    mut ev_intervention: EventReader<InterventionEvent>,
+    mut ev_peril: EventWriter<PerilEvent>,
+    mut ev_visitation: EventWriter<VisitationEvent>,
+) {
+    let mut rng = thread_rng();
+
+    for event in ev_intervention.read() {
+        if event.kind() != InterventionKind::BeastAttack {
+            continue;
+        }
+        let deed = if rng.gen_bool(MAULING_CHANCE) {
+            ev_peril.send(PerilEvent::new(event.target(), "Mauled by beasts"));
+            "sends beasts to maul"
+        } else {
+            "sends beasts to scar"
+        };
+        ev_visitation.send(VisitationEvent::new(
+            event.house().clone(),
+            event.kind(),
+            event.target(),
+            deed,
+            false,
+        ));
+    }
+}
+
+/// Fire takes someone's barn, and their standing with it
+fn burn_barns(
+    mut ev_intervention: EventReader<InterventionEvent>,
+    mut ev_visitation: EventWriter<VisitationEvent>,
+    mut query_prestige: Query<&mut Prestige>,
+) {
+    for event in ev_intervention.read() {
+        if event.kind() != InterventionKind::Blaze {
+            continue;
+        }
+        if let Ok(mut prestige) = query_prestige.get_mut(event.target()) {
+            prestige.adjust(BLAZE_PRESTIGE);
+        }
+        ev_visitation.send(VisitationEvent::new(
+            event.house().clone(),
+            event.kind(),
+            event.target(),
+            "burns down the barn of",
+            false,
+        ));
+    }
+}
+
+/// Light wards those House Death has a claim on, and forgives any who wouldn't pay their debt.
+/// The mark of the house that spared them, and what they owe it, remain.
+fn heal_the_sick(
+    mut ev_intervention: EventReader<InterventionEvent>,
+    mut ev_visitation: EventWriter<VisitationEvent>,
+    mut query_touched: Query<&mut TouchedByHouse>,
+) {
+    for event in ev_intervention.read() {
+        if event.kind() != InterventionKind::Healing {
+            continue;
+        }
+        let deed = if let Ok(mut touched) = query_touched.get_mut(event.target()) {
+            touched.forsaken = false;
+            touched.warded = true;
+            "wards off House Death from"
+        } else {
+            "grants good health to"
+        };
+        ev_visitation.send(VisitationEvent::new(
+            event.house().clone(),
+            event.kind(),
+            event.target(),
+            deed,
+            true,
+        ));
+    }
+}
+
+/// Spread word of what the houses have done, and let those visited remember it
+fn tell_visitation_stories(
+    mut ev_visitation: EventReader<VisitationEvent>,
+    query_calendar: Query<&Calendar>,
+    mut query_people: Query<(&Name, &mut Memories)>,
+) {
+    let date = query_calendar.single().date();
+
+    for event in ev_visitation.read() {
+        let Ok((name, mut memories)) = query_people.get_mut(event.target()) else {
+            continue;
+        };
+        info!(
+            "House {} {} {} {}",
+            event.house(),
+            event.deed(),
+            name.first,
+            name.last
+        );
+        let weight = if event.is_boon() {
+            BOON_WEIGHT
+        } else {
+            CURSE_WEIGHT
+        };
+        memories.remember(
+            MemoryKind::Visitation {
+                house: event.house().clone(),
+                kind: event.kind(),
+            },
+            None,
+            date,
+            weight,
+        );
+    }
+}
+
+/// Read the intervention table once logging is up, so a missing file is reported
+fn load_interventions(mut commands: Commands) {
+    let interventions = Interventions::load(INTERVENTIONS_PATH).unwrap_or_else(|error| {
+        warn!(
+            "Couldn't read {}, using the built-in interventions: {}",
+            INTERVENTIONS_PATH, error
+        );
+        Interventions::default()
+    });
+    commands.insert_resource(interventions);
+}
+
+pub struct InterventionPlugin;
+
+impl Plugin for InterventionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, load_interventions)
+            .add_event::<InterventionEvent>()
+            .add_event::<VisitationEvent>()
+            .add_systems(
+                Update,
+                (
+                    stir_the_houses,
+                    (send_visions, loose_beasts, burn_barns, heal_the_sick),
+                    tell_visitation_stories,
+                )
+                    .chain()
+                    .run_if(in_state(RunState::Running)),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shipped_table_parses() {
+        let interventions: Interventions =
+            ron::from_str(include_str!("../assets/interventions.ron")).unwrap();
+        assert!(!interventions.table.is_empty());
+    }
+}
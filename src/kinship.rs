@@ -0,0 +1,386 @@
+use bevy::{
+    ecs::system::SystemParam,
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+
+use crate::{
+    marriage::Spouse,
+    people::{Children, Parents},
+    sex::Gender,
+};
+
+/// How far up the family tree to look for a common ancestor
+const MAX_KINSHIP_DEPTH: u32 = 8;
+
+/// Pick the word for someone's gender, e.g. `["brother", "sister", "sibling"]`
+fn gendered(gender: Option<Gender>, words: [&str; 3]) -> &str {
+    match gender {
+        Some(Gender::Man) => words[0],
+        Some(Gender::Woman) => words[1],
+        Some(Gender::NonBinary) | None => words[2],
+    }
+}
+
+/// "first", "second", ... for cousins
+fn ordinal(n: u32) -> String {
+    match n {
+        1 => "first".into(),
+        2 => "second".into(),
+        3 => "third".into(),
+        4 => "fourth".into(),
+        5 => "fifth".into(),
+        _ => format!("{n}th"),
+    }
+}
+
+/// "once removed", "twice removed", ... for cousins of different generations
+fn removed(n: u32) -> String {
+    match n {
+        0 => String::new(),
+        1 => " once removed".into(),
+        2 => " twice removed".into(),
+        3 => " thrice removed".into(),
+        _ => format!(" {n} times removed"),
+    }
+}
+
+/// "great-" repeated, for going further up or down the tree
+fn greats(n: u32) -> String {
+    "great-".repeat(n as usize)
+}
+
+/// Name a blood relation from how many generations up to the common ancestor `up` is, and back
+/// down `down`. `full_blood` is whether siblings share both parents.
+pub fn relation_name(up: u32, down: u32, gender: Option<Gender>, full_blood: bool) -> String {
+    match (up, down) {
+        (0, 1) => gendered(gender, ["son", "daughter", "child"]).into(),
+        (0, _) => format!(
+            "{}grand{}",
+            greats(down - 2),
+            gendered(gender, ["son", "daughter", "child"])
+        ),
+        (1, 0) => gendered(gender, ["father", "mother", "parent"]).into(),
+        (_, 0) => format!(
+            "{}grand{}",
+            greats(up - 2),
+            gendered(gender, ["father", "mother", "parent"])
+        ),
+        (1, 1) => {
+            let half = if full_blood { "" } else { "half-" };
+            format!(
+                "{half}{}",
+                gendered(gender, ["brother", "sister", "sibling"])
+            )
+        }
+        (1, 2) => gendered(gender, ["nephew", "niece", "nibling"]).into(),
+        (1, _) => format!(
+            "{}grand{}",
+            greats(down - 3),
+            gendered(gender, ["nephew", "niece", "nibling"])
+        ),
+        (2, 1) => gendered(gender, ["uncle", "aunt", "pibling"]).into(),
+        (_, 1) => format!(
+            "{}{}",
+            greats(up - 2),
+            gendered(gender, ["uncle", "aunt", "pibling"])
+        ),
+        _ => format!(
+            "{} cousin{}",
+            ordinal(up.min(down) - 1),
+            removed(up.abs_diff(down))
+        ),
+    }
+}
+
+/// How someone is related by marriage, through their own spouse or through the person's spouse
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InLaw {
+    Parent,
+    Sibling,
+    Child,
+}
+
+/// Name an in-law, e.g. "mother-in-law"
+pub fn in_law_name(in_law: InLaw, gender: Option<Gender>) -> String {
+    let words = match in_law {
+        InLaw::Parent => ["father", "mother", "parent"],
+        InLaw::Sibling => ["brother", "sister", "sibling"],
+        InLaw::Child => ["son", "daughter", "child"],
+    };
+    format!("{}-in-law", gendered(gender, words))
+}
+
+/// Breadth-first through the generations, keeping the closest distance to each relative
+fn walk(
+    person: Entity,
+    depth: u32,
+    next: impl Fn(Entity) -> HashSet<Entity>,
+) -> HashMap<Entity, u32> {
+    let mut found = HashMap::new();
+    let mut generation = HashSet::from([person]);
+    for distance in 1..=depth {
+        generation = generation
+            .iter()
+            .flat_map(|person| next(*person))
+            .filter(|relative| !found.contains_key(relative))
+            .collect();
+        if generation.is_empty() {
+            break;
+        }
+        for relative in &generation {
+            found.insert(*relative, distance);
+        }
+    }
+    found
+}
+
+/// Questions about how people are related, walking the family tree from any system
+#[derive(SystemParam)]
+pub struct Kinship<'w, 's> {
+    query: Query<
+        'w,
+        's,
+        (
+            &'static Parents,
+            &'static Children,
+            Option<&'static Spouse>,
+            Option<&'static Gender>,
+        ),
+    >,
+}
+
+impl Kinship<'_, '_> {
+    pub fn parents(&self, person: Entity) -> HashSet<Entity> {
+        self.query
+            .get(person)
+            .map(|(parents, _, _, _)| parents.iter().copied().collect())
+            .unwrap_or_default()
+    }
+
+    pub fn children(&self, person: Entity) -> HashSet<Entity> {
+        self.query
+            .get(person)
+            .map(|(_, children, _, _)| children.iter().copied().collect())
+            .unwrap_or_default()
+    }
+
+    pub fn spouse(&self, person: Entity) -> Option<Entity> {
+        self.query
+            .get(person)
+            .ok()
+            .and_then(|(_, _, spouse, _)| spouse.map(|spouse| spouse.entity))
+    }
+
+    fn gender(&self, person: Entity) -> Option<Gender> {
+        self.query
+            .get(person)
+            .ok()
+            .and_then(|(_, _, _, gender)| gender.copied())
+    }
+
+    /// Everyone up to `depth` generations up, with how many generations up they are
+    pub fn ancestors(&self, person: Entity, depth: u32) -> HashMap<Entity, u32> {
+        walk(person, depth, |person| self.parents(person))
+    }
+
+    /// Everyone up to `depth` generations down, with how many generations down they are
+    pub fn descendants(&self, person: Entity, depth: u32) -> HashMap<Entity, u32> {
+        walk(person, depth, |person| self.children(person))
+    }
+
+    /// Anyone sharing at least one parent
+    pub fn siblings(&self, person: Entity) -> HashSet<Entity> {
+        self.parents(person)
+            .iter()
+            .flat_map(|parent| self.children(*parent))
+            .filter(|sibling| *sibling != person)
+            .collect()
+    }
+
+    /// Parents, children, siblings and spouse, the people whose deaths are mourned
+    pub fn close_kin(&self, person: Entity) -> HashSet<Entity> {
+        self.parents(person)
+            .into_iter()
+            .chain(self.children(person))
+            .chain(self.siblings(person))
+            .chain(self.spouse(person))
+            .collect()
+    }
+
+    /// Siblings sharing both parents
+    pub fn full_siblings(&self, person: Entity) -> HashSet<Entity> {
+        let parents = self.parents(person);
+        self.siblings(person)
+            .into_iter()
+            .filter(|sibling| self.parents(*sibling) == parents)
+            .collect()
+    }
+
+    /// Siblings sharing only one parent
+    pub fn half_siblings(&self, person: Entity) -> HashSet<Entity> {
+        let full = self.full_siblings(person);
+        self.siblings(person)
+            .into_iter()
+            .filter(|sibling| !full.contains(sibling))
+            .collect()
+    }
+
+    pub fn aunts_and_uncles(&self, person: Entity) -> HashSet<Entity> {
+        self.parents(person)
+            .iter()
+            .flat_map(|parent| self.siblings(*parent))
+            .collect()
+    }
+
+    /// First cousins, the children of aunts and uncles
+    pub fn cousins(&self, person: Entity) -> HashSet<Entity> {
+        self.aunts_and_uncles(person)
+            .iter()
+            .flat_map(|aunt_or_uncle| self.children(*aunt_or_uncle))
+            .collect()
+    }
+
+    /// A spouse's parents and siblings, siblings' spouses and children's spouses
+    pub fn in_laws(&self, person: Entity) -> HashSet<Entity> {
+        let mut in_laws = HashSet::new();
+        if let Some(spouse) = self.spouse(person) {
+            in_laws.extend(self.parents(spouse));
+            in_laws.extend(self.siblings(spouse));
+        }
+        in_laws.extend(
+            self.siblings(person)
+                .iter()
+                .chain(&self.children(person))
+                .filter_map(|relative| self.spouse(*relative)),
+        );
+        in_laws
+    }
+
+    /// The closest ancestor two people share, with how many generations up it is from each of them.
+    /// Someone counts as their own ancestor here, so a parent is the common ancestor of them and their child.
+    pub fn common_ancestor(&self, first: Entity, second: Entity) -> Option<(Entity, u32, u32)> {
+        let mut first_line = self.ancestors(first, MAX_KINSHIP_DEPTH);
+        first_line.insert(first, 0);
+        let mut second_line = self.ancestors(second, MAX_KINSHIP_DEPTH);
+        second_line.insert(second, 0);
+
+        first_line
+            .iter()
+            .filter_map(|(ancestor, up_first)| {
+                second_line
+                    .get(ancestor)
+                    .map(|up_second| (*ancestor, *up_first, *up_second))
+            })
+            .min_by_key(|(_, up_first, up_second)| up_first + up_second)
+    }
+
+    /// How `other` is related to `person`, e.g. "second cousin once removed" or "mother-in-law"
+    pub fn relation(&self, person: Entity, other: Entity) -> Option<String> {
+        if person == other {
+            return None;
+        }
+        let gender = self.gender(other);
+
+        if self.spouse(person) == Some(other) || self.spouse(other) == Some(person) {
+            return Some(gendered(gender, ["husband", "wife", "spouse"]).into());
+        }
+
+        if let Some((_, up, down)) = self.common_ancestor(person, other) {
+            let full_blood = (up, down) != (1, 1) || self.full_siblings(person).contains(&other);
+            return Some(relation_name(up, down, gender, full_blood));
+        }
+
+        self.in_law_relation(person, other, gender)
+    }
+
+    fn in_law_relation(
+        &self,
+        person: Entity,
+        other: Entity,
+        gender: Option<Gender>,
+    ) -> Option<String> {
+        let in_law = if let Some(spouse) = self.spouse(person) {
+            if self.parents(spouse).contains(&other) {
+                Some(InLaw::Parent)
+            } else if self.siblings(spouse).contains(&other) {
+                Some(InLaw::Sibling)
+            } else {
+                None
+            }
+        } else {
+            None
+        };
+
+        let in_law = in_law.or_else(|| {
+            let spouse = self.spouse(other)?;
+            if self.siblings(person).contains(&spouse) {
+                Some(InLaw::Sibling)
+            } else if self.children(person).contains(&spouse) {
+                Some(InLaw::Child)
+            } else {
+                None
+            }
+        })?;
+
+        Some(in_law_name(in_law, gender))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAN: Option<Gender> = Some(Gender::Man);
+    const WOMAN: Option<Gender> = Some(Gender::Woman);
+
+    #[test]
+    fn names_direct_lines() {
+        assert_eq!(relation_name(1, 0, WOMAN, true), "mother");
+        assert_eq!(relation_name(0, 1, None, true), "child");
+        assert_eq!(relation_name(2, 0, MAN, true), "grandfather");
+        assert_eq!(
+            relation_name(0, 4, WOMAN, true),
+            "great-great-granddaughter"
+        );
+    }
+
+    #[test]
+    fn names_siblings_by_blood() {
+        assert_eq!(relation_name(1, 1, MAN, true), "brother");
+        assert_eq!(relation_name(1, 1, MAN, false), "half-brother");
+        assert_eq!(
+            relation_name(1, 1, Some(Gender::NonBinary), false),
+            "half-sibling"
+        );
+    }
+
+    #[test]
+    fn names_aunts_uncles_and_niblings() {
+        assert_eq!(relation_name(2, 1, WOMAN, true), "aunt");
+        assert_eq!(relation_name(3, 1, MAN, true), "great-uncle");
+        assert_eq!(relation_name(1, 2, MAN, true), "nephew");
+        assert_eq!(relation_name(1, 3, WOMAN, true), "grandniece");
+    }
+
+    #[test]
+    fn names_cousins() {
+        assert_eq!(relation_name(2, 2, MAN, true), "first cousin");
+        assert_eq!(relation_name(3, 3, WOMAN, true), "second cousin");
+        assert_eq!(
+            relation_name(3, 4, None, true),
+            "second cousin once removed"
+        );
+        assert_eq!(
+            relation_name(4, 2, None, true),
+            "first cousin twice removed"
+        );
+    }
+
+    #[test]
+    fn names_in_laws() {
+        assert_eq!(in_law_name(InLaw::Parent, WOMAN), "mother-in-law");
+        assert_eq!(in_law_name(InLaw::Sibling, MAN), "brother-in-law");
+        assert_eq!(in_law_name(InLaw::Child, None), "child-in-law");
+    }
+}
@@ -1,11 +1,35 @@
-use crate::{moon::MoonHouse, people::Name, state::RunState};
-use bevy::prelude::*;
+use crate::{
+    age::Age,
+    kinship::Kinship,
+    moon::{Moon, MoonHouse},
+    people::{AssignedMoonHouse, Name},
+    personality::Personality,
+    royalty::Prestige,
+    state::RunState,
+};
+use bevy::{prelude::*, utils::HashSet};
+use rand::prelude::*;
+
+/// Chance someone at death's door is spared, if the house they were born under is ascendant
+const CHEAT_DEATH_CHANCE: f64 = 0.1;
+/// Chance each day of falling gravely ill at `ILLNESS_AGE`, rising steeply with age
+const ILLNESS_CHANCE: f64 = 0.0005;
+const ILLNESS_AGE: f64 = 60.0;
+/// Chance each day House Death comes for someone who was spared
+const RECLAIM_CHANCE: f64 = 0.0005;
+/// How much harder House Death hunts while ascendant, and again for those who wouldn't pay
+const RECLAIM_FURY: f64 = 2.0;
+/// The house that spared someone calls in its debt somewhere in this many days
+const DEBT_DAYS: std::ops::Range<u32> = 360..1800;
+const DEBT_PRESTIGE: i32 = -50;
+/// How much paying a debt sways the heavens towards the house that was owed
+const DEBT_FAVOUR: u32 = 5;
 
 #[derive(Component, Debug)]
 pub struct Alive;
 
 #[derive(Component, Debug)]
-struct Deceased;
+pub struct Deceased;
 
 #[derive(Event, Debug)]
 pub struct DeathEvent {
@@ -20,6 +44,48 @@ impl DeathEvent {
             cause: cause.into(),
         }
     }
+
+    pub fn dying(&self) -> Entity {
+        self.dying
+    }
+
+    pub fn cause(&self) -> &str {
+        &self.cause
+    }
+}
+
+/// Each death once, however many people killed the same person in the same frame
+pub fn distinct_deaths<'a>(
+    ev_death: &'a mut EventReader<DeathEvent>,
+) -> impl Iterator<Item = &'a DeathEvent> + 'a {
+    let mut dead = HashSet::new();
+    ev_death
+        .read()
+        .filter(move |event| dead.insert(event.dying))
+}
+
+/// Someone is at death's door, and only their house can save them now
+#[derive(Event, Debug)]
+pub struct PerilEvent {
+    endangered: Entity,
+    cause: String,
+}
+
+impl PerilEvent {
+    pub fn new(endangered: Entity, cause: &str) -> Self {
+        PerilEvent {
+            endangered,
+            cause: cause.into(),
+        }
+    }
+
+    pub fn endangered(&self) -> Entity {
+        self.endangered
+    }
+
+    pub fn cause(&self) -> &str {
+        &self.cause
+    }
 }
 
 #[derive(Event, Debug)]
@@ -32,6 +98,14 @@ impl CheatDeathEvent {
     pub fn new(cheater: Entity, house: MoonHouse) -> Self {
         CheatDeathEvent { cheater, house }
     }
+
+    pub fn cheater(&self) -> Entity {
+        self.cheater
+    }
+
+    pub fn house(&self) -> &MoonHouse {
+        &self.house
+    }
 }
 
 fn handle_death(
@@ -39,8 +113,11 @@ fn handle_death(
     mut ev_death: EventReader<DeathEvent>,
     query: Query<&Name, With<Alive>>,
 ) {
-    for event in ev_death.read() {
-        let name = query.get(event.dying).unwrap();
+    for event in distinct_deaths(&mut ev_death) {
+        // with poison, battles and the headsman about, someone can be killed twice over
+        let Ok(name) = query.get(event.dying) else {
+            continue;
+        };
         debug!("Handling death event for {} {}", name.first, name.last);
 
         commands
@@ -52,23 +129,179 @@ fn handle_death(
             "{} {} died. Cause of death: {}",
             name.first, name.last, event.cause
         );
+    }
+}
+
+fn mourn_the_dead(
+    mut ev_death: EventReader<DeathEvent>,
+    kinship: Kinship,
+    query_dying: Query<&Name>,
+    query_mourners: Query<(&Name, &Personality), With<Alive>>,
+) {
+    for event in distinct_deaths(&mut ev_death) {
+        let Ok(name) = query_dying.get(event.dying) else {
+            continue;
+        };
+
+        for (mourner, personality) in query_mourners.iter_many(&kinship.close_kin(event.dying)) {
+            info!(
+                "{} {} {} after the death of {} {}",
+                mourner.first,
+                mourner.last,
+                personality.grief_reaction(),
+                name.first,
+                name.last
+            );
+        }
+    }
+}
+
+/// Marked by the house that spared them from death, which will want repaying
+#[derive(Component, Debug)]
+pub struct TouchedByHouse {
+    pub house: MoonHouse,
+    /// How many times they've been spared
+    pub times: u32,
+    /// Days until the house calls in its debt, if it's still owed
+    pub debt_due: Option<u32>,
+    /// Refused to pay, and so lost the house's protection
+    pub forsaken: bool,
+    /// Healed by House Light, which keeps House Death further off
+    pub warded: bool,
+}
+
+/// Those at death's door die, unless the house they were born under is ascendant and spares them.
+/// A house won't save anyone who refused to pay it what they owed.
+fn face_peril(
+    mut ev_peril: EventReader<PerilEvent>,
+    mut ev_death: EventWriter<DeathEvent>,
+    mut ev_cheat_death: EventWriter<CheatDeathEvent>,
+    query_moon: Query<&Moon>,
+    query: Query<(&AssignedMoonHouse, Option<&TouchedByHouse>), With<Alive>>,
+) {
+    let moon = query_moon.single();
+    let mut rng = thread_rng();
+
+    for event in ev_peril.read() {
+        let Ok((house, touched)) = query.get(event.endangered()) else {
+            continue;
+        };
+        let forsaken = touched.is_some_and(|touched| touched.forsaken);
+        if house.house == moon.house && !forsaken && rng.gen_bool(CHEAT_DEATH_CHANCE) {
+            ev_cheat_death.send(CheatDeathEvent::new(
+                event.endangered(),
+                house.house.clone(),
+            ));
+        } else {
+            ev_death.send(DeathEvent::new(event.endangered(), event.cause()));
+        }
+    }
+}
+
+/// The old fall gravely ill, and House Death hunts those who were spared
+fn fall_ill(
+    mut ev_peril: EventWriter<PerilEvent>,
+    query_moon: Query<&Moon>,
+    query: Query<(Entity, &Age, Option<&TouchedByHouse>), With<Alive>>,
+) {
+    let moon = query_moon.single();
+    let mut rng = thread_rng();
 
-        // TODO: make the parents/siblings/children sad
+    for (person, age, touched) in &query {
+        let illness = ILLNESS_CHANCE * (f64::from(age.years()) / ILLNESS_AGE).powi(4);
+        if rng.gen_bool(illness.min(1.0)) {
+            ev_peril.send(PerilEvent::new(person, "Illness"));
+            continue;
+        }
+
+        let Some(touched) = touched else {
+            continue;
+        };
+        let mut reclaim = RECLAIM_CHANCE;
+        if moon.house == MoonHouse::Death {
+            reclaim *= RECLAIM_FURY;
+        }
+        if touched.forsaken {
+            reclaim *= RECLAIM_FURY;
+        }
+        if touched.warded {
+            reclaim /= RECLAIM_FURY;
+        }
+        if rng.gen_bool(reclaim) {
+            ev_peril.send(PerilEvent::new(person, "Reclaimed by House Death"));
+        }
     }
 }
 
+/// Those who cheat death are marked by the house that spared them, and owe it a debt
 fn handle_cheat_death(
+    mut commands: Commands,
     mut ev_cheat_death: EventReader<CheatDeathEvent>,
-    query: Query<&Name, With<Alive>>,
+    mut query: Query<(&Name, &Personality, Option<&mut TouchedByHouse>), With<Alive>>,
 ) {
+    let mut rng = thread_rng();
+
     for event in ev_cheat_death.read() {
-        let name = query.get(event.cheater).unwrap();
-        debug!(
-            "Handling cheat death event for {} {}",
-            name.first, name.last
+        let Ok((name, personality, touched)) = query.get_mut(event.cheater) else {
+            continue;
+        };
+        info!(
+            "{} {} cheated death and {}",
+            name.first,
+            name.last,
+            personality.cheat_death_reaction()
         );
-        // TODO: make the houses get mad at each other?
-        // When someone cheats death, house death gets mad at the house that helped them
+
+        let debt_due = Some(rng.gen_range(DEBT_DAYS));
+        if let Some(mut touched) = touched {
+            touched.times += 1;
+            touched.debt_due = debt_due;
+        } else {
+            commands.entity(event.cheater).insert(TouchedByHouse {
+                house: event.house.clone(),
+                times: 1,
+                debt_due,
+                forsaken: false,
+                warded: false,
+            });
+        }
+    }
+}
+
+/// In time the house that spared someone calls in its debt. The pious and loyal pay with their
+/// renown and their offerings sway the heavens, the rest refuse and lose its protection.
+fn call_in_debts(
+    mut query_moon: Query<&mut Moon>,
+    mut query: Query<(&Name, &Personality, &mut TouchedByHouse, &mut Prestige), With<Alive>>,
+) {
+    let mut moon = query_moon.single_mut();
+    let mut rng = thread_rng();
+
+    for (name, personality, mut touched, mut prestige) in &mut query {
+        let Some(days) = touched.debt_due else {
+            continue;
+        };
+        if days > 0 {
+            touched.debt_due = Some(days - 1);
+            continue;
+        }
+
+        touched.debt_due = None;
+        let willingness = f64::midpoint(personality.piety, personality.loyalty);
+        if rng.gen_bool(willingness.clamp(0.05, 0.95)) {
+            prestige.adjust(DEBT_PRESTIGE);
+            moon.favour(&touched.house, DEBT_FAVOUR);
+            info!(
+                "House {} calls in its debt, and {} {} pays it in full",
+                touched.house, name.first, name.last
+            );
+        } else {
+            touched.forsaken = true;
+            warn!(
+                "House {} calls in its debt, but {} {} refuses it. House Death draws near",
+                touched.house, name.first, name.last
+            );
+        }
     }
 }
 
@@ -78,8 +311,17 @@ impl Plugin for LifePlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(
             Update,
-            (handle_death, handle_cheat_death).run_if(in_state(RunState::Running)),
+            (
+                fall_ill,
+                face_peril,
+                handle_death,
+                mourn_the_dead,
+                handle_cheat_death,
+                call_in_debts,
+            )
+                .run_if(in_state(RunState::Running)),
         )
+        .add_event::<PerilEvent>()
         .add_event::<DeathEvent>()
         .add_event::<CheatDeathEvent>();
     }
@@ -1,16 +1,39 @@
 #![warn(clippy::all, clippy::pedantic)]
 mod age;
 mod calendar;
+mod ceremony;
+mod crime;
+mod crisis;
+mod dynasty;
+mod faction;
+mod family_check;
 mod frames;
+mod household;
+mod intervention;
+mod kinship;
 mod life;
+mod marriage;
+mod memories;
 mod moon;
+mod naming;
 mod people;
+mod personality;
+mod prophecy;
+mod regency;
+mod relationships;
+mod religion;
 mod reproduction;
 mod season;
 mod setup;
+mod sex;
+mod social;
 mod state;
+mod succession;
+mod titles;
+mod traits;
 mod weather;
 mod royalty;
+mod scheme;
 
 use age::AgePlugin;
 use bevy::{
@@ -19,30 +42,70 @@ use bevy::{
     state::app::StatesPlugin,
 };
 use calendar::DatePlugin;
+use ceremony::CeremonyPlugin;
+use crime::CrimePlugin;
+use crisis::CrisisPlugin;
+use dynasty::DynastyPlugin;
+use faction::FactionPlugin;
+use family_check::FamilyCheckPlugin;
 use frames::FramePlugin;
+use household::HouseholdPlugin;
+use intervention::InterventionPlugin;
 use life::LifePlugin;
+use marriage::MarriagePlugin;
+use memories::MemoryPlugin;
 use moon::MoonPlugin;
+use naming::NamingPlugin;
 use people::HelloPlugin;
+use prophecy::ProphecyPlugin;
+use regency::RegencyPlugin;
+use relationships::RelationshipPlugin;
+use religion::ReligionPlugin;
 use reproduction::ReproductionPlugin;
 use season::SeasonPlugin;
 use setup::SetupPlugin;
+use sex::SexPlugin;
+use social::SocialPlugin;
 use state::PausePlugin;
+use succession::SuccessionPlugin;
+use titles::TitlePlugin;
 use royalty::RoyaltyPlugin;
+use scheme::SchemePlugin;
 
 fn main() {
     App::new()
         .add_plugins(MinimalPlugins)
         .add_plugins(StatesPlugin) // needed to use states
         .add_plugins(FramePlugin) // add framecount resource
+        .add_plugins(NamingPlugin)
+        .add_plugins(SexPlugin)
         .add_plugins(SetupPlugin)
         .add_plugins(HelloPlugin)
+        .add_plugins(FamilyCheckPlugin)
         .add_plugins(AgePlugin)
         .add_plugins(ReproductionPlugin)
         .add_plugins(LifePlugin)
+        .add_plugins(MarriagePlugin)
+        .add_plugins(HouseholdPlugin)
+        .add_plugins(RelationshipPlugin)
+        .add_plugins(SocialPlugin)
+        .add_plugins(MemoryPlugin)
         .add_plugins(DatePlugin)
         .add_plugins(MoonPlugin)
         .add_plugins(SeasonPlugin)
         .add_plugins(RoyaltyPlugin)
+        .add_plugins(SuccessionPlugin)
+        .add_plugins(RegencyPlugin)
+        .add_plugins(DynastyPlugin)
+        .add_plugins(TitlePlugin)
+        .add_plugins(CeremonyPlugin)
+        .add_plugins(CrisisPlugin)
+        .add_plugins(FactionPlugin)
+        .add_plugins(SchemePlugin)
+        .add_plugins(CrimePlugin)
+        .add_plugins(ReligionPlugin)
+        .add_plugins(ProphecyPlugin)
+        .add_plugins(InterventionPlugin)
         .add_plugins(PausePlugin) // adds RunState and toggle based on frame count
         .add_plugins(LogPlugin {
             level: Level::DEBUG,
@@ -0,0 +1,285 @@
+use bevy::{prelude::*, utils::HashSet};
+use rand::prelude::*;
+
+use crate::{
+    age::Age,
+    household::{Household, InHousehold},
+    kinship::Kinship,
+    life::{distinct_deaths, Alive, DeathEvent},
+    people::{Name, Person},
+    personality::Personality,
+    sex::Sex,
+    state::RunState,
+};
+
+const MARRIAGEABLE_AGE: i32 = 16;
+const DAILY_MARRIAGE_CHANCE: f64 = 0.01;
+const DAILY_DIVORCE_CHANCE: f64 = 0.0002;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PartnershipEnd {
+    Widowed,
+    Divorced,
+}
+
+#[derive(Debug, Clone)]
+pub struct Partnership {
+    pub partner: Entity,
+    pub ended: Option<PartnershipEnd>,
+}
+
+/// Every partnership a person has been in, oldest first
+#[derive(Component, Debug, Default)]
+pub struct Partners {
+    pub history: Vec<Partnership>,
+}
+
+impl Partners {
+    pub fn married_to(partner: Entity) -> Self {
+        Partners {
+            history: vec![Partnership {
+                partner,
+                ended: None,
+            }],
+        }
+    }
+
+    fn end(&mut self, partner: Entity, how: PartnershipEnd) {
+        for partnership in &mut self.history {
+            if partnership.partner == partner && partnership.ended.is_none() {
+                partnership.ended = Some(how);
+            }
+        }
+    }
+}
+
+/// The person someone is currently married to
+#[derive(Component, Debug)]
+pub struct Spouse {
+    pub entity: Entity,
+}
+
+#[derive(Event, Debug)]
+pub struct MarriageEvent {
+    first: Entity,
+    second: Entity,
+}
+
+impl MarriageEvent {
+    pub fn new(first: Entity, second: Entity) -> Self {
+        MarriageEvent { first, second }
+    }
+
+    pub fn first(&self) -> Entity {
+        self.first
+    }
+
+    pub fn second(&self) -> Entity {
+        self.second
+    }
+}
+
+#[derive(Event, Debug)]
+pub struct DivorceEvent {
+    first: Entity,
+    second: Entity,
+}
+
+impl DivorceEvent {
+    pub fn new(first: Entity, second: Entity) -> Self {
+        DivorceEvent { first, second }
+    }
+
+    pub fn first(&self) -> Entity {
+        self.first
+    }
+
+    pub fn second(&self) -> Entity {
+        self.second
+    }
+}
+
+fn arrange_marriages(
+    mut ev_marriage: EventWriter<MarriageEvent>,
+    kinship: Kinship,
+    query: Query<(Entity, &Age, &Sex, &Personality), (With<Person>, With<Alive>, Without<Spouse>)>,
+) {
+    let mut rng = thread_rng();
+    let mut spoken_for = HashSet::new();
+
+    // marriages are between someone who can bear children and someone who can't
+    let brides: Vec<(Entity, &Personality)> = query
+        .iter()
+        .filter(|(_, age, sex, _)| age.years() >= MARRIAGEABLE_AGE && sex.can_bear_children())
+        .map(|(bride, _, _, personality)| (bride, personality))
+        .collect();
+
+    for (suitor, age, sex, personality) in &query {
+        // the lustier you are, the keener you are to court
+        let courting_chance = DAILY_MARRIAGE_CHANCE * 2.0 * personality.lust;
+        if age.years() < MARRIAGEABLE_AGE
+            || sex.can_bear_children()
+            || !rng.gen_bool(courting_chance)
+        {
+            continue;
+        }
+
+        // no marrying your close family
+        let close_kin = kinship.close_kin(suitor);
+        let candidates: Vec<&(Entity, &Personality)> = brides
+            .iter()
+            .filter(|(bride, _)| !spoken_for.contains(bride) && !close_kin.contains(bride))
+            .collect();
+
+        // people like people like themselves
+        if let Ok((bride, _)) = candidates.choose_weighted(&mut rng, |(_, bride_personality)| {
+            personality.compatibility(bride_personality)
+        }) {
+            spoken_for.insert(*bride);
+            ev_marriage.send(MarriageEvent::new(suitor, *bride));
+        }
+    }
+}
+
+fn arrange_divorces(
+    mut ev_divorce: EventWriter<DivorceEvent>,
+    query: Query<(Entity, &Spouse, &Personality)>,
+) {
+    let mut rng = thread_rng();
+
+    for (person, spouse, personality) in &query {
+        // only roll once per couple
+        if person > spouse.entity {
+            continue;
+        }
+        let Ok((_, _, spouse_personality)) = query.get(spouse.entity) else {
+            continue;
+        };
+
+        // loyal couples stick together, mismatched ones drift apart
+        let disloyalty = 2.0 - personality.loyalty - spouse_personality.loyalty;
+        let mismatch = 1.0 - personality.compatibility(spouse_personality);
+        if rng.gen_bool((DAILY_DIVORCE_CHANCE * disloyalty * (1.0 + mismatch)).min(1.0)) {
+            ev_divorce.send(DivorceEvent::new(person, spouse.entity));
+        }
+    }
+}
+
+fn handle_marriage(
+    mut commands: Commands,
+    mut ev_marriage: EventReader<MarriageEvent>,
+    mut query: Query<(&Name, &mut Partners, Option<&InHousehold>), (With<Alive>, Without<Spouse>)>,
+) {
+    for event in ev_marriage.read() {
+        let Ok(
+            [(first_name, mut first_partners, first_home), (second_name, mut second_partners, second_home)],
+        ) = query.get_many_mut([event.first, event.second])
+        else {
+            debug!("Can't marry {} and {}", event.first, event.second);
+            continue;
+        };
+
+        first_partners.history.push(Partnership {
+            partner: event.second,
+            ended: None,
+        });
+        second_partners.history.push(Partnership {
+            partner: event.first,
+            ended: None,
+        });
+
+        // the second partner moves in with the first, unless only the second has a home
+        let household = match (first_home, second_home) {
+            (Some(home), _) | (None, Some(home)) => home.household,
+            (None, None) => commands.spawn(Household).id(),
+        };
+
+        commands.entity(event.first).insert((
+            Spouse {
+                entity: event.second,
+            },
+            InHousehold { household },
+        ));
+        commands.entity(event.second).insert((
+            Spouse {
+                entity: event.first,
+            },
+            InHousehold { household },
+        ));
+
+        info!(
+            "{} {} married {} {}",
+            first_name.first, first_name.last, second_name.first, second_name.last
+        );
+    }
+}
+
+fn handle_divorce(
+    mut commands: Commands,
+    mut ev_divorce: EventReader<DivorceEvent>,
+    mut query: Query<(&Name, &mut Partners), With<Spouse>>,
+) {
+    for event in ev_divorce.read() {
+        let Ok([(first_name, mut first_partners), (second_name, mut second_partners)]) =
+            query.get_many_mut([event.first, event.second])
+        else {
+            debug!("Can't divorce {} and {}", event.first, event.second);
+            continue;
+        };
+
+        first_partners.end(event.second, PartnershipEnd::Divorced);
+        second_partners.end(event.first, PartnershipEnd::Divorced);
+
+        commands.entity(event.first).remove::<Spouse>();
+        // the second partner moves out and sets up their own home
+        let household = commands.spawn(Household).id();
+        commands
+            .entity(event.second)
+            .remove::<Spouse>()
+            .insert(InHousehold { household });
+
+        info!(
+            "{} {} and {} {} divorced",
+            first_name.first, first_name.last, second_name.first, second_name.last
+        );
+    }
+}
+
+fn handle_widowhood(
+    mut commands: Commands,
+    mut ev_death: EventReader<DeathEvent>,
+    mut query: Query<(&Name, &mut Partners, &Spouse)>,
+) {
+    for event in distinct_deaths(&mut ev_death) {
+        let Ok((_, mut dying_partners, spouse)) = query.get_mut(event.dying()) else {
+            continue;
+        };
+        let widow = spouse.entity;
+        dying_partners.end(widow, PartnershipEnd::Widowed);
+        commands.entity(event.dying()).remove::<Spouse>();
+
+        if let Ok((name, mut widow_partners, _)) = query.get_mut(widow) {
+            widow_partners.end(event.dying(), PartnershipEnd::Widowed);
+            commands.entity(widow).remove::<Spouse>();
+            info!("{} {} was widowed", name.first, name.last);
+        }
+    }
+}
+
+pub struct MarriagePlugin;
+
+impl Plugin for MarriagePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                (arrange_marriages, arrange_divorces),
+                (handle_marriage, handle_divorce, handle_widowhood),
+            )
+                .chain()
+                .run_if(in_state(RunState::Running)),
+        )
+        .add_event::<MarriageEvent>()
+        .add_event::<DivorceEvent>();
+    }
+}
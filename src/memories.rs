@@ -0,0 +1,305 @@
+use bevy::prelude::*;
+
+use crate::{
+    calendar::{Calendar, Date},
+    ceremony::CeremonyKind,
+    crime::CrimeKind,
+    intervention::InterventionKind,
+    kinship::Kinship,
+    life::{distinct_deaths, Alive, CheatDeathEvent, DeathEvent},
+    marriage::{DivorceEvent, MarriageEvent},
+    moon::{ExaltEvent, MoonHouse},
+    personality::Personality,
+    relationships::Relationships,
+    reproduction::BirthEvent,
+    scheme::SchemeKind,
+    state::RunState,
+};
+
+/// How much of a memory's weight is left after each day, roughly halving every two years
+const DAILY_FADE: f64 = 0.999;
+/// Memories weaker than this are forgotten
+const FORGET_THRESHOLD: f64 = 0.05;
+/// Memories stronger than this still colour how someone feels
+const VIVID_THRESHOLD: f64 = 0.3;
+const BIRTH_WEIGHT: f64 = 0.8;
+const MARRIAGE_WEIGHT: f64 = 0.7;
+const DIVORCE_WEIGHT: f64 = -0.6;
+const EXALT_WEIGHT: f64 = 0.4;
+const CHEAT_DEATH_WEIGHT: f64 = 0.9;
+const SHARED_HAPPINESS_WEIGHT: f64 = 0.2;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MemoryKind {
+    ChildBorn,
+    /// How the dead were related to the person remembering, e.g. "mother"
+    KinDied {
+        relation: String,
+    },
+    Married,
+    Divorced,
+    Exalted {
+        house: MoonHouse,
+    },
+    CheatedDeath {
+        house: MoonHouse,
+    },
+    /// A royal ceremony, either held for them or attended as a guest
+    Ceremony {
+        ceremony: CeremonyKind,
+        honoured: bool,
+    },
+    Affair,
+    /// Found out someone was scheming against them
+    Plotted {
+        scheme: SchemeKind,
+    },
+    /// Turned to the worship of another house
+    Converted {
+        house: MoonHouse,
+    },
+    /// Singled out by a house for a boon or a curse
+    Visitation {
+        house: MoonHouse,
+        kind: InterventionKind,
+    },
+    /// A case they were the accused or the victim in
+    Trial {
+        crime: CrimeKind,
+        accused: bool,
+        convicted: bool,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct Memory {
+    pub kind: MemoryKind,
+    /// Who else the memory is about, if anyone
+    pub about: Option<Entity>,
+    pub date: Date,
+    /// How strongly it's felt, positive for happy memories and negative for painful ones
+    pub weight: f64,
+}
+
+impl Memory {
+    /// What happened, e.g. "the death of their mother"
+    pub fn describe(&self) -> String {
+        match &self.kind {
+            MemoryKind::ChildBorn => "the birth of their child".into(),
+            MemoryKind::KinDied { relation } => format!("the death of their {relation}"),
+            MemoryKind::Married => "their wedding".into(),
+            MemoryKind::Divorced => "their divorce".into(),
+            MemoryKind::Exalted { house } => format!("being exalted by {house}"),
+            MemoryKind::CheatedDeath { house } => format!("being spared by {house}"),
+            MemoryKind::Ceremony {
+                ceremony,
+                honoured: true,
+            } => format!("their {ceremony}"),
+            MemoryKind::Ceremony {
+                ceremony,
+                honoured: false,
+            } => format!("attending a royal {ceremony}"),
+            MemoryKind::Affair => "their affair".into(),
+            MemoryKind::Plotted { scheme } => format!("uncovering a plot to {}", scheme.aim()),
+            MemoryKind::Converted { house } => format!("their conversion to House {house}"),
+            MemoryKind::Visitation { house, kind } => format!("{kind} sent by House {house}"),
+            MemoryKind::Trial {
+                crime,
+                accused: true,
+                convicted: true,
+            } => format!("being convicted of {crime}"),
+            MemoryKind::Trial {
+                crime,
+                accused: true,
+                convicted: false,
+            } => format!("being acquitted of {crime}"),
+            MemoryKind::Trial {
+                crime,
+                accused: false,
+                convicted: true,
+            } => format!("seeing justice done for the {crime} against them"),
+            MemoryKind::Trial {
+                crime,
+                accused: false,
+                convicted: false,
+            } => format!("the {crime} against them going unpunished"),
+        }
+    }
+}
+
+/// The significant things that have happened to someone, most recent last
+#[derive(Component, Debug, Default)]
+pub struct Memories {
+    pub events: Vec<Memory>,
+}
+
+impl Memories {
+    pub fn remember(&mut self, kind: MemoryKind, about: Option<Entity>, date: Date, weight: f64) {
+        self.events.push(Memory {
+            kind,
+            about,
+            date,
+            weight,
+        });
+    }
+
+    /// The memory that weighs on someone most, happy or not
+    pub fn strongest(&self) -> Option<&Memory> {
+        self.events
+            .iter()
+            .max_by(|a, b| a.weight.abs().total_cmp(&b.weight.abs()))
+    }
+
+    /// How someone's past still shapes them, e.g. "still bitter about the death of their mother"
+    pub fn recall(&self) -> Option<String> {
+        let memory = self
+            .strongest()
+            .filter(|memory| memory.weight.abs() >= VIVID_THRESHOLD)?;
+        let feeling = if memory.weight < 0.0 {
+            "still bitter about"
+        } else {
+            "still fond of"
+        };
+        Some(format!(
+            "{feeling} {} on {}",
+            memory.describe(),
+            memory.date
+        ))
+    }
+}
+
+fn remember_family_events(
+    mut ev_birth: EventReader<BirthEvent>,
+    mut ev_marriage: EventReader<MarriageEvent>,
+    mut ev_divorce: EventReader<DivorceEvent>,
+    query_calendar: Query<&Calendar>,
+    mut query: Query<&mut Memories>,
+) {
+    let date = query_calendar.single().date();
+
+    for event in ev_birth.read() {
+        for parent in [event.mother(), event.father()] {
+            if let Ok(mut memories) = query.get_mut(parent) {
+                memories.remember(
+                    MemoryKind::ChildBorn,
+                    Some(event.child()),
+                    date,
+                    BIRTH_WEIGHT,
+                );
+            }
+        }
+    }
+
+    for event in ev_marriage.read() {
+        for (person, spouse) in [
+            (event.first(), event.second()),
+            (event.second(), event.first()),
+        ] {
+            if let Ok(mut memories) = query.get_mut(person) {
+                memories.remember(MemoryKind::Married, Some(spouse), date, MARRIAGE_WEIGHT);
+            }
+        }
+    }
+
+    for event in ev_divorce.read() {
+        for (person, spouse) in [
+            (event.first(), event.second()),
+            (event.second(), event.first()),
+        ] {
+            if let Ok(mut memories) = query.get_mut(person) {
+                memories.remember(MemoryKind::Divorced, Some(spouse), date, DIVORCE_WEIGHT);
+            }
+        }
+    }
+}
+
+fn remember_deaths(
+    relationships: Res<Relationships>,
+    kinship: Kinship,
+    mut ev_death: EventReader<DeathEvent>,
+    query_calendar: Query<&Calendar>,
+    mut query_mourners: Query<(Entity, &mut Memories, &Personality), With<Alive>>,
+) {
+    let date = query_calendar.single().date();
+
+    for event in distinct_deaths(&mut ev_death) {
+        let dying = event.dying();
+        let kin = kinship.close_kin(dying);
+        let mut mourners = query_mourners.iter_many_mut(&kin);
+        while let Some((mourner, mut memories, personality)) = mourners.fetch_next() {
+            let Some(relation) = kinship.relation(mourner, dying) else {
+                continue;
+            };
+            // the closer they were, the more it hurts
+            let fondness = f64::from(relationships.opinion(mourner, dying).max(0)) / 100.0;
+            let mut weight = -(0.3 + 0.3 * personality.loyalty + 0.4 * fondness);
+            // happy times together make the loss harder
+            if memories
+                .events
+                .iter()
+                .any(|memory| memory.about == Some(dying) && memory.weight > 0.0)
+            {
+                weight -= SHARED_HAPPINESS_WEIGHT;
+            }
+            memories.remember(MemoryKind::KinDied { relation }, Some(dying), date, weight);
+        }
+    }
+}
+
+fn remember_the_houses(
+    mut ev_exalt: EventReader<ExaltEvent>,
+    mut ev_cheat_death: EventReader<CheatDeathEvent>,
+    query_calendar: Query<&Calendar>,
+    mut query: Query<&mut Memories>,
+) {
+    let date = query_calendar.single().date();
+
+    for event in ev_exalt.read() {
+        if let Ok(mut memories) = query.get_mut(event.exalted()) {
+            let house = event.house().clone();
+            memories.remember(MemoryKind::Exalted { house }, None, date, EXALT_WEIGHT);
+        }
+    }
+
+    for event in ev_cheat_death.read() {
+        if let Ok(mut memories) = query.get_mut(event.cheater()) {
+            let house = event.house().clone();
+            memories.remember(
+                MemoryKind::CheatedDeath { house },
+                None,
+                date,
+                CHEAT_DEATH_WEIGHT,
+            );
+        }
+    }
+}
+
+/// Time heals, or at least dulls
+fn fade_memories(mut query: Query<&mut Memories, With<Alive>>) {
+    for mut memories in &mut query {
+        for memory in &mut memories.events {
+            memory.weight *= DAILY_FADE;
+        }
+        memories
+            .events
+            .retain(|memory| memory.weight.abs() >= FORGET_THRESHOLD);
+    }
+}
+
+pub struct MemoryPlugin;
+
+impl Plugin for MemoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                remember_family_events,
+                remember_deaths,
+                remember_the_houses,
+                fade_memories,
+            )
+                .chain()
+                .run_if(in_state(RunState::Running)),
+        );
+    }
+}
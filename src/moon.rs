@@ -3,6 +3,7 @@ use bevy::prelude::*;
 use bevy::utils::HashMap;
 use rand::distributions::Standard;
 use rand::prelude::*;
+use serde::Deserialize;
 use std::fmt;
 
 use crate::life::Alive;
@@ -37,6 +38,13 @@ impl Moon {
             house_weights,
         }
     }
+
+    /// Make a house more likely to be the next to rise
+    pub fn favour(&mut self, house: &MoonHouse, amount: u32) {
+        if let Some(weight) = self.house_weights.get_mut(house) {
+            *weight += amount;
+        }
+    }
 }
 
 #[derive(Debug, Eq, PartialEq)]
@@ -93,7 +101,7 @@ impl fmt::Display for MoonPhase {
     }
 }
 
-#[derive(Debug, Eq, Hash, PartialEq, Clone)]
+#[derive(Debug, Eq, Hash, PartialEq, Clone, Deserialize)]
 pub enum MoonHouse {
     Dark,
     Light,
@@ -108,6 +116,19 @@ pub enum MoonHouse {
 }
 
 impl MoonHouse {
+    pub const ALL: [MoonHouse; 10] = [
+        MoonHouse::Dark,
+        MoonHouse::Light,
+        MoonHouse::Fire,
+        MoonHouse::Water,
+        MoonHouse::Wind,
+        MoonHouse::Earth,
+        MoonHouse::Death,
+        MoonHouse::Storm,
+        MoonHouse::Dream,
+        MoonHouse::Wild,
+    ];
+
     pub fn str(&self) -> String {
         use MoonHouse::{Dark, Death, Dream, Earth, Fire, Light, Storm, Water, Wild, Wind};
         match *self {
@@ -210,16 +231,39 @@ impl FromWorld for ExaltSystem {
     }
 }
 
+/// Someone was exalted by the house they were born under
+#[derive(Event, Debug)]
+pub struct ExaltEvent {
+    exalted: Entity,
+    house: MoonHouse,
+}
+
+impl ExaltEvent {
+    pub fn new(exalted: Entity, house: MoonHouse) -> Self {
+        ExaltEvent { exalted, house }
+    }
+
+    pub fn exalted(&self) -> Entity {
+        self.exalted
+    }
+
+    pub fn house(&self) -> &MoonHouse {
+        &self.house
+    }
+}
+
 /// Basic implementation of a one-shot system to see how they work
 fn exalt_house_members(
-    person_query: Query<(&Name, &AssignedMoonHouse), With<Alive>>,
+    mut ev_exalt: EventWriter<ExaltEvent>,
+    person_query: Query<(Entity, &Name, &AssignedMoonHouse), With<Alive>>,
     moon_query: Query<&Moon>,
 ) {
     let moon = moon_query.get_single().unwrap();
 
-    for (name, house) in &person_query {
+    for (person, name, house) in &person_query {
         if house.house == moon.house {
             info!("{} exalts {} {}", moon.house, name.first, name.last);
+            ev_exalt.send(ExaltEvent::new(person, moon.house.clone()));
         }
     }
 }
@@ -231,6 +275,7 @@ impl Plugin for MoonPlugin {
         app.add_systems(Startup, add_moon);
         app.add_systems(Update, handle_moon.run_if(in_state(RunState::Running)));
         app.init_resource::<ExaltSystem>(); // for the exalt one-shot
+        app.add_event::<ExaltEvent>();
     }
 }
 
@@ -0,0 +1,361 @@
+use bevy::{
+    ecs::system::SystemParam,
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+use rand::{distributions::Standard, prelude::*};
+
+use crate::{
+    life::Deceased,
+    people::{Children, Name, Parents},
+    sex::Gender,
+};
+
+/// The naming tradition a person was brought up in
+#[derive(Component, Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Culture {
+    #[default]
+    Kingdom,
+    Southron,
+    Northern,
+    Islander,
+}
+
+impl Distribution<Culture> for Standard {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Culture {
+        use Culture::{Islander, Kingdom, Northern, Southron};
+        match rng.gen_range(0..5) {
+            0 => Southron,
+            1 => Northern,
+            2 => Islander,
+            _ => Kingdom,
+        }
+    }
+}
+
+/// How a child's surname is made from their parents' names
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SurnameRule {
+    /// Take the father's surname
+    Patrilineal,
+    /// Take the mother's surname
+    Matrilineal,
+    /// Mother's surname then father's, e.g. Morales-Allan
+    DoubleBarrelled,
+    /// Father's first name with a suffix, e.g. Eriksson or Eriksdottir
+    Patronymic,
+}
+
+#[derive(Debug)]
+pub struct CultureNames {
+    pub male: Vec<&'static str>,
+    pub female: Vec<&'static str>,
+    pub surnames: Vec<&'static str>,
+}
+
+impl CultureNames {
+    pub fn first_names(&self, gender: Gender) -> Vec<&'static str> {
+        match gender {
+            Gender::Man => self.male.clone(),
+            Gender::Woman => self.female.clone(),
+            Gender::NonBinary => [self.male.as_slice(), self.female.as_slice()].concat(),
+        }
+    }
+}
+
+/// Lists of names to pick from for each culture
+#[derive(Resource, Debug)]
+pub struct NameLists {
+    pub cultures: HashMap<Culture, CultureNames>,
+}
+
+impl Default for NameLists {
+    fn default() -> Self {
+        // TODO: read the lists from file?
+        let mut cultures = HashMap::new();
+        cultures.insert(
+            Culture::Kingdom,
+            CultureNames {
+                male: vec![
+                    "Tim", "Tom", "Lachlan", "Alex", "Jack", "Chris", "Harry", "Stephen", "Paul",
+                    "Albert", "Jacob", "Edmund",
+                ],
+                female: vec![
+                    "Paulina", "Alex", "Lillian", "Pia", "Tess", "Phoebe", "Kim", "Myra",
+                    "Claudia", "Penny", "Pip", "Maud",
+                ],
+                surnames: vec![
+                    "Allan",
+                    "Miller",
+                    "Smith",
+                    "Baker",
+                    "Tanner",
+                    "Peddler",
+                    "Freeman",
+                    "Butcher",
+                    "Carpenter",
+                    "Farrier",
+                    "Primrose",
+                    "Stubbs",
+                    "McNaughton",
+                    "Morris",
+                    "Ploughman",
+                    "Bowyer",
+                    "Tinker",
+                    "Wilmot",
+                ],
+            },
+        );
+        cultures.insert(
+            Culture::Southron,
+            CultureNames {
+                male: vec![
+                    "Mateo", "Diego", "Rafael", "Tomas", "Joaquin", "Andres", "Felipe", "Emilio",
+                    "Santiago", "Ignacio",
+                ],
+                female: vec![
+                    "Lucia", "Carmen", "Ines", "Rosa", "Elena", "Pilar", "Marisol", "Beatriz",
+                    "Alba", "Paloma",
+                ],
+                surnames: vec![
+                    "Morales", "Garcia", "Navarro", "Ortega", "Castillo", "Romero", "Delgado",
+                    "Vidal", "Herrera", "Molina",
+                ],
+            },
+        );
+        cultures.insert(
+            Culture::Northern,
+            CultureNames {
+                male: vec![
+                    "Erik", "Leif", "Bjorn", "Sven", "Ulf", "Gunnar", "Harald", "Ivar", "Arne",
+                    "Torstein",
+                ],
+                female: vec![
+                    "Astrid", "Ingrid", "Sigrid", "Freya", "Helga", "Gudrun", "Solveig", "Ragna",
+                    "Tove", "Eira",
+                ],
+                // patronymic, so surnames are built from first names instead
+                surnames: vec![],
+            },
+        );
+
+        cultures.insert(
+            Culture::Islander,
+            CultureNames {
+                male: vec![
+                    "Kai", "Tane", "Rangi", "Manu", "Nikau", "Ari", "Tama", "Hemi", "Koa", "Wiremu",
+                ],
+                female: vec![
+                    "Moana", "Aroha", "Hana", "Kiri", "Marama", "Ana", "Leilani", "Mere", "Tui",
+                    "Ngaio",
+                ],
+                surnames: vec![
+                    "Reefwalker",
+                    "Saltwind",
+                    "Tidecaller",
+                    "Shellcomb",
+                    "Driftwood",
+                    "Palmer",
+                    "Wavecrest",
+                    "Sandpiper",
+                ],
+            },
+        );
+
+        NameLists { cultures }
+    }
+}
+
+/// Configuration for how newborns are named
+#[derive(Resource, Debug)]
+pub struct NamingRules {
+    pub surname_rules: HashMap<Culture, SurnameRule>,
+    /// Chance a child is named after a dead relative, if there is one to honour
+    pub honour_dead_chance: f64,
+}
+
+impl Default for NamingRules {
+    fn default() -> Self {
+        let mut surname_rules = HashMap::new();
+        surname_rules.insert(Culture::Kingdom, SurnameRule::Patrilineal);
+        surname_rules.insert(Culture::Southron, SurnameRule::DoubleBarrelled);
+        surname_rules.insert(Culture::Northern, SurnameRule::Patronymic);
+        surname_rules.insert(Culture::Islander, SurnameRule::Matrilineal);
+
+        NamingRules {
+            surname_rules,
+            honour_dead_chance: 0.3,
+        }
+    }
+}
+
+impl NamingRules {
+    pub fn surname(
+        &self,
+        culture: Culture,
+        father: &Name,
+        mother: &Name,
+        gender: Gender,
+    ) -> String {
+        let rule = self
+            .surname_rules
+            .get(&culture)
+            .copied()
+            .unwrap_or(SurnameRule::Patrilineal);
+
+        match rule {
+            SurnameRule::Patrilineal => father.last.clone(),
+            SurnameRule::Matrilineal => mother.last.clone(),
+            SurnameRule::DoubleBarrelled => {
+                // only the first part of each parent's name carries on
+                let maternal = mother.last.split('-').next().unwrap_or_default();
+                let paternal = father.last.split('-').next().unwrap_or_default();
+                if maternal == paternal {
+                    maternal.into()
+                } else {
+                    format!("{maternal}-{paternal}")
+                }
+            }
+            SurnameRule::Patronymic => patronym(&father.first, gender),
+        }
+    }
+}
+
+fn patronym(father_first: &str, gender: Gender) -> String {
+    match gender {
+        Gender::Man => format!("{father_first}sson"),
+        Gender::Woman => format!("{father_first}sdottir"),
+        Gender::NonBinary => format!("{father_first}sbur"),
+    }
+}
+
+/// Pick a surname for someone whose parents aren't known, e.g. the first people in the kingdom
+pub fn founder_surname(
+    lists: &NameLists,
+    rules: &NamingRules,
+    culture: Culture,
+    gender: Gender,
+    rng: &mut ThreadRng,
+) -> String {
+    let names = &lists.cultures[&culture];
+    let rule = rules.surname_rules.get(&culture);
+    if names.surnames.is_empty() || rule == Some(&SurnameRule::Patronymic) {
+        patronym(names.male.choose(rng).unwrap(), gender)
+    } else {
+        (*names.surnames.choose(rng).unwrap()).into()
+    }
+}
+
+/// Everything needed to name a newborn after their family
+#[derive(SystemParam)]
+pub struct Namer<'w, 's> {
+    lists: Res<'w, NameLists>,
+    rules: Res<'w, NamingRules>,
+    query_culture: Query<'w, 's, &'static Culture>,
+    query_family: Query<'w, 's, (&'static Parents, &'static Children)>,
+    query_names: Query<'w, 's, (&'static Name, Option<&'static Gender>, Has<Deceased>)>,
+}
+
+impl Namer<'_, '_> {
+    /// Choose a name for a baby, returning the culture they'll be brought up in too
+    pub fn name_newborn(
+        &self,
+        mother: Entity,
+        father: Entity,
+        gender: Gender,
+        born_with: &HashSet<String>,
+        rng: &mut ThreadRng,
+    ) -> (Name, Culture) {
+        // children are raised in their mother's culture
+        let culture = self.query_culture.get(mother).copied().unwrap_or_default();
+
+        let last = match (self.query_names.get(father), self.query_names.get(mother)) {
+            (Ok((father_name, _, _)), Ok((mother_name, _, _))) => {
+                self.rules
+                    .surname(culture, father_name, mother_name, gender)
+            }
+            _ => founder_surname(&self.lists, &self.rules, culture, gender, rng),
+        };
+
+        // don't reuse the name of a brother or sister who's still around
+        let mut taken = born_with.clone();
+        for parent in [mother, father] {
+            if let Ok((_, children)) = self.query_family.get(parent) {
+                for (name, _, deceased) in self.query_names.iter_many(children.iter()) {
+                    if !deceased {
+                        taken.insert(name.first.clone());
+                    }
+                }
+            }
+        }
+
+        let first = self
+            .honoured_name(mother, father, gender, &taken, rng)
+            .unwrap_or_else(|| {
+                let names = self.lists.cultures[&culture].first_names(gender);
+                let untaken: Vec<&str> = names
+                    .iter()
+                    .copied()
+                    .filter(|name| !taken.contains(*name))
+                    .collect();
+                // if every name is taken then doubling up is the best we can do
+                let pool = if untaken.is_empty() { names } else { untaken };
+                (*pool.choose(rng).unwrap()).into()
+            });
+
+        (Name { first, last }, culture)
+    }
+
+    /// Maybe pick the name of a dead sibling, parent, grandparent, aunt or uncle of the same gender
+    fn honoured_name(
+        &self,
+        mother: Entity,
+        father: Entity,
+        gender: Gender,
+        taken: &HashSet<String>,
+        rng: &mut ThreadRng,
+    ) -> Option<String> {
+        if !rng.gen_bool(self.rules.honour_dead_chance) {
+            return None;
+        }
+
+        let mut relatives = HashSet::from([mother, father]);
+        for parent in [mother, father] {
+            let Ok((grandparents, children)) = self.query_family.get(parent) else {
+                continue;
+            };
+            // brothers and sisters who didn't make it
+            relatives.extend(children.iter());
+            for grandparent in grandparents.iter() {
+                relatives.insert(*grandparent);
+                if let Ok((_, aunts_and_uncles)) = self.query_family.get(*grandparent) {
+                    relatives.extend(aunts_and_uncles.iter());
+                }
+            }
+        }
+
+        let honoured: Vec<String> = self
+            .query_names
+            .iter_many(&relatives)
+            .filter(|(name, relative_gender, deceased)| {
+                *deceased && *relative_gender == Some(&gender) && !taken.contains(&name.first)
+            })
+            .map(|(name, _, _)| name.first.clone())
+            .collect();
+
+        let chosen = honoured.choose(rng).cloned();
+        if let Some(name) = &chosen {
+            debug!("Naming a baby {} in memory of a dead relative", name);
+        }
+        chosen
+    }
+}
+
+pub struct NamingPlugin;
+
+impl Plugin for NamingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<NameLists>()
+            .init_resource::<NamingRules>();
+    }
+}
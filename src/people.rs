@@ -1,32 +1,165 @@
-use bevy::{prelude::*, utils::HashSet};
+use std::ops::Deref;
+
+use bevy::{
+    ecs::{component::ComponentId, world::DeferredWorld},
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+use rand::thread_rng;
 
 use crate::age::Age;
+use crate::calendar::Calendar;
+use crate::kinship::Kinship;
 use crate::life::Alive;
+use crate::marriage::Partners;
+use crate::memories::Memories;
 use crate::moon::MoonHouse;
+use crate::naming::Culture;
+use crate::personality::Personality;
+use crate::relationships::Relationships;
+use crate::royalty::Prestige;
+use crate::sex::{Gender, Sex};
 use crate::state::RunState;
+use crate::traits::Traits;
 
+/// Despawning someone erases them from everyone's relationships
 #[derive(Component, Debug)]
+#[component(on_remove = erase_from_relationships)]
 pub struct Person;
 
-#[derive(Component, Debug)]
+fn erase_from_relationships(mut world: DeferredWorld, person: Entity, _: ComponentId) {
+    if let Some(mut relationships) = world.get_resource_mut::<Relationships>() {
+        relationships.erase(person);
+    }
+}
+
+#[derive(Component, Debug, Clone)]
 pub struct Name {
     pub first: String,
     pub last: String,
 }
 
-#[derive(Component, Debug)]
+/// Who someone's parents are. This is the one place parenthood is stored: inserting it
+/// links the child into each parent's `Children`, and replacing, removing or despawning unlinks them.
+#[derive(Component, Debug, Default)]
+#[require(Children)]
+#[component(on_insert = link_to_parents, on_replace = unlink_from_parents)]
 pub struct Parents {
-    pub set: HashSet<Entity>,
+    set: HashSet<Entity>,
 }
 
-#[derive(Component, Debug)]
+impl Parents {
+    pub fn new(set: HashSet<Entity>) -> Self {
+        Parents { set }
+    }
+}
+
+impl Deref for Parents {
+    type Target = HashSet<Entity>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.set
+    }
+}
+
+/// Who someone's children are. Kept in step with the children's `Parents`, so never set directly.
+#[derive(Component, Debug, Default)]
+#[component(on_remove = orphan_children)]
 pub struct Children {
-    pub set: HashSet<Entity>,
+    set: HashSet<Entity>,
 }
 
-#[derive(Component, Debug)]
-pub struct Siblings {
-    pub set: HashSet<Entity>,
+impl Deref for Children {
+    type Target = HashSet<Entity>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.set
+    }
+}
+
+fn link_to_parents(mut world: DeferredWorld, child: Entity, _: ComponentId) {
+    let parents = world.get::<Parents>(child).unwrap().set.clone();
+    for parent in parents {
+        if let Some(mut children) = world.get_mut::<Children>(parent) {
+            children.set.insert(child);
+        } else if world.get_entity(parent).is_ok() {
+            // merge rather than insert, other children may be linked to them before this applies
+            world.commands().queue(move |world: &mut World| {
+                let Ok(mut parent) = world.get_entity_mut(parent) else {
+                    return;
+                };
+                if let Some(mut children) = parent.get_mut::<Children>() {
+                    children.set.insert(child);
+                } else {
+                    parent.insert(Children {
+                        set: HashSet::from([child]),
+                    });
+                }
+            });
+        }
+    }
+}
+
+fn unlink_from_parents(mut world: DeferredWorld, child: Entity, _: ComponentId) {
+    let parents = world.get::<Parents>(child).unwrap().set.clone();
+    for parent in parents {
+        if let Some(mut children) = world.get_mut::<Children>(parent) {
+            children.set.remove(&child);
+        }
+    }
+}
+
+/// A parent is gone for good, so their children no longer point at them
+fn orphan_children(mut world: DeferredWorld, parent: Entity, _: ComponentId) {
+    let children = world.get::<Children>(parent).unwrap().set.clone();
+    for child in children {
+        if let Some(mut parents) = world.get_mut::<Parents>(child) {
+            parents.set.remove(&parent);
+        }
+    }
+}
+
+/// Work out everyone's `Children` again from their children's `Parents`, for repairing the family tree
+pub fn rebuild_children(world: &mut World) {
+    let mut all_children: HashMap<Entity, HashSet<Entity>> = HashMap::new();
+    for (child, parents) in world.query::<(Entity, &Parents)>().iter(world) {
+        for parent in &parents.set {
+            all_children.entry(*parent).or_default().insert(child);
+        }
+    }
+
+    for (parent, mut children) in world.query::<(Entity, &mut Children)>().iter_mut(world) {
+        children.set = all_children.remove(&parent).unwrap_or_default();
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Zygosity {
+    Identical,
+    Fraternal,
+}
+
+/// Siblings born at the same time, and whether they're identical
+#[derive(Component, Debug, Default)]
+#[component(on_remove = unlink_twins)]
+pub struct Twins {
+    pub zygosity: HashMap<Entity, Zygosity>,
+}
+
+/// A twin is gone for good, so the others no longer point at them
+fn unlink_twins(mut world: DeferredWorld, person: Entity, _: ComponentId) {
+    let twins: Vec<Entity> = world
+        .get::<Twins>(person)
+        .unwrap()
+        .zygosity
+        .keys()
+        .copied()
+        .collect();
+    for twin in twins {
+        if let Some(mut twins) = world.get_mut::<Twins>(twin) {
+            twins.zygosity.remove(&person);
+        }
+    }
 }
 
 #[derive(Component, Debug)]
@@ -39,10 +172,18 @@ pub struct PersonBundle {
     pub person: Person,
     pub alive: Alive,
     pub name: Name,
+    pub sex: Sex,
+    pub gender: Gender,
     pub parents: Parents,
     pub children: Children,
-    pub siblings: Siblings,
+    pub twins: Twins,
+    pub partners: Partners,
     pub moon_house: AssignedMoonHouse,
+    pub culture: Culture,
+    pub traits: Traits,
+    pub personality: Personality,
+    pub memories: Memories,
+    pub prestige: Prestige,
     pub age: Age,
 }
 
@@ -50,8 +191,8 @@ impl PersonBundle {
     pub fn new_child(
         first: &str,
         last: &str,
+        sex: Sex,
         parents: HashSet<Entity>,
-        siblings: HashSet<Entity>,
         house: MoonHouse,
         age: i32,
     ) -> Self {
@@ -62,17 +203,23 @@ impl PersonBundle {
                 first: first.into(),
                 last: last.into(),
             },
-            parents: Parents { set: parents },
-            children: Children {
-                set: HashSet::new(),
-            },
-            siblings: Siblings { set: siblings },
+            sex,
+            gender: sex.into(),
+            parents: Parents::new(parents),
+            children: Children::default(),
+            twins: Twins::default(),
+            partners: Partners::default(),
             moon_house: AssignedMoonHouse { house },
+            culture: Culture::default(),
+            traits: Traits::random(&mut thread_rng()),
+            personality: Personality::random(&mut thread_rng()),
+            memories: Memories::default(),
+            prestige: Prestige::default(),
             age: Age::new(age),
         }
     }
 
-    pub fn initial_people(first: &str, last: &str, house: MoonHouse, age: i32) -> Self {
+    pub fn initial_people(first: &str, last: &str, sex: Sex, house: MoonHouse, age: i32) -> Self {
         PersonBundle {
             person: Person,
             alive: Alive,
@@ -80,61 +227,138 @@ impl PersonBundle {
                 first: first.into(),
                 last: last.into(),
             },
-            parents: Parents {
-                set: HashSet::new(),
-            },
-            children: Children {
-                set: HashSet::new(),
-            },
-            siblings: Siblings {
-                set: HashSet::new(),
-            },
+            sex,
+            gender: sex.into(),
+            parents: Parents::default(),
+            children: Children::default(),
+            twins: Twins::default(),
+            partners: Partners::default(),
             moon_house: AssignedMoonHouse { house },
+            culture: Culture::default(),
+            traits: Traits::random(&mut thread_rng()),
+            personality: Personality::random(&mut thread_rng()),
+            memories: Memories::default(),
+            prestige: Prestige::default(),
             age: Age::new(age),
         }
     }
 }
 
+/// Introduce everyone and their family once a month, walking the whole family tree is too costly
+/// to do every day
 fn greet_people(
+    query_calendar: Query<&Calendar>,
+    relationships: Res<Relationships>,
+    kinship: Kinship,
+    query_memories: Query<&Memories>,
     query_people: Query<
         (
+            Entity,
             &Name,
             &Age,
             &Children,
             &Parents,
-            &Siblings,
+            &Twins,
             &AssignedMoonHouse,
         ),
         With<Person>,
     >,
 ) {
-    for (name, age, children, parents, siblings, assigned_house) in &query_people {
+    if query_calendar.single().date().month_day != 1 {
+        return;
+    }
+
+    for (person, name, age, children, parents, twins, assigned_house) in &query_people {
         debug!("Hello {} {}({})!", name.first, name.last, age);
         debug!(
             "{} {} is favoured by High House {}",
             name.first, name.last, assigned_house.house
         );
 
-        for (child_name, _, _, _, _, _) in query_people.iter_many(&children.set) {
+        if let Some(recollection) = query_memories.get(person).ok().and_then(Memories::recall) {
+            debug!("{} {} is {}", name.first, name.last, recollection);
+        }
+
+        for (_, child_name, _, _, _, _, _) in query_people.iter_many(children.iter()) {
             debug!(
                 "{} {} has a child called {} {}",
                 name.first, name.last, child_name.first, child_name.last,
             );
         }
 
-        for (parent_name, _, _, _, _, _) in query_people.iter_many(&parents.set) {
+        for (_, parent_name, _, _, _, _, _) in query_people.iter_many(parents.iter()) {
             debug!(
                 "{} {} has a parent called {} {}",
                 name.first, name.last, parent_name.first, parent_name.last,
             );
         }
 
-        for (sibling_name, _, _, _, _, _) in query_people.iter_many(&siblings.set) {
+        let half_siblings = kinship.half_siblings(person);
+        for (sibling, sibling_name, _, _, _, _, _) in
+            query_people.iter_many(&kinship.siblings(person))
+        {
+            let half = if half_siblings.contains(&sibling) {
+                "half-"
+            } else {
+                ""
+            };
+            debug!(
+                "{} {} has a {}sibling: {} {}",
+                name.first, name.last, half, sibling_name.first, sibling_name.last
+            );
+        }
+
+        let descendants = kinship.descendants(person, 3).len();
+        if descendants > children.len() {
             debug!(
-                "{} {} has a sibling: {} {}",
-                name.first, name.last, sibling_name.first, sibling_name.last
+                "{} {} has {} descendants",
+                name.first, name.last, descendants
             );
         }
+
+        for (twin, zygosity) in &twins.zygosity {
+            if let Ok((_, twin_name, _, _, _, _, _)) = query_people.get(*twin) {
+                debug!(
+                    "{} {} has an {:?} twin: {} {}",
+                    name.first, name.last, zygosity, twin_name.first, twin_name.last
+                );
+            }
+        }
+
+        // further out in the family tree
+        let extended_family = kinship
+            .ancestors(person, 2)
+            .into_keys()
+            .chain(kinship.aunts_and_uncles(person))
+            .chain(kinship.cousins(person))
+            .chain(kinship.in_laws(person));
+        for relative in extended_family {
+            if let (Some(relation), Ok((_, relative_name, _, _, _, _, _))) = (
+                kinship.relation(person, relative),
+                query_people.get(relative),
+            ) {
+                debug!(
+                    "{} {}'s {} is {} {}",
+                    name.first, name.last, relation, relative_name.first, relative_name.last
+                );
+            }
+        }
+
+        for (other, relationship) in relationships.known_by(person) {
+            if let (Some(kind), Ok((_, other_name, _, _, _, _, _))) =
+                (relationship.kind, query_people.get(other))
+            {
+                debug!(
+                    "{} {} sees {} {} as their {:?} ({})",
+                    name.first,
+                    name.last,
+                    other_name.first,
+                    other_name.last,
+                    kind,
+                    relationship.opinion
+                );
+            }
+        }
     }
 }
 
@@ -145,3 +369,38 @@ impl Plugin for HelloPlugin {
         app.add_systems(Update, greet_people.run_if(in_state(RunState::Running)));
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn children_linked_together_are_all_kept() {
+        let mut world = World::new();
+        let parent = world.spawn_empty().id();
+        let linked = || Parents::new(HashSet::from([parent]));
+        let children: HashSet<Entity> = world.spawn_batch([linked(), linked()]).collect();
+        world.flush();
+
+        assert_eq!(**world.get::<Children>(parent).unwrap(), children);
+    }
+
+    #[test]
+    fn despawned_twins_are_unlinked() {
+        let mut world = World::new();
+        let first = world.spawn(Twins::default()).id();
+        let second = world
+            .spawn(Twins {
+                zygosity: HashMap::from([(first, Zygosity::Identical)]),
+            })
+            .id();
+        world
+            .get_mut::<Twins>(first)
+            .unwrap()
+            .zygosity
+            .insert(second, Zygosity::Identical);
+
+        world.despawn(second);
+        assert!(world.get::<Twins>(first).unwrap().zygosity.is_empty());
+    }
+}
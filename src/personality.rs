@@ -0,0 +1,134 @@
+use bevy::prelude::*;
+use rand::prelude::*;
+use statrs::distribution::Normal;
+
+use crate::moon::MoonHouse;
+
+/// How far a child's personality can stray from their parents'
+const PERSONALITY_STD: f64 = 0.15;
+/// How strongly the house a child is born under shapes them
+const HOUSE_NUDGE: f64 = 0.15;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Facet {
+    Ambition,
+    Piety,
+    Cruelty,
+    Loyalty,
+    Lust,
+}
+
+/// What drives someone's choices, each facet from 0 to 1
+#[derive(Component, Debug, Clone)]
+pub struct Personality {
+    pub ambition: f64,
+    pub piety: f64,
+    pub cruelty: f64,
+    pub loyalty: f64,
+    pub lust: f64,
+}
+
+impl Personality {
+    pub fn random(rng: &mut ThreadRng) -> Self {
+        Personality {
+            ambition: rng.gen(),
+            piety: rng.gen(),
+            cruelty: rng.gen(),
+            loyalty: rng.gen(),
+            lust: rng.gen(),
+        }
+    }
+
+    /// A child takes after both parents, with a nudge from the house they're born under
+    pub fn inherit(
+        mother: &Personality,
+        father: &Personality,
+        house: &MoonHouse,
+        rng: &mut ThreadRng,
+    ) -> Self {
+        let noise = Normal::new(0.0, PERSONALITY_STD).unwrap();
+        let mut blend = |facet: Facet| {
+            let mean = f64::midpoint(mother.facet(facet), father.facet(facet));
+            let nudge = if house_favours(house) == facet {
+                HOUSE_NUDGE
+            } else {
+                0.0
+            };
+            (mean + nudge + noise.sample(rng)).clamp(0.0, 1.0)
+        };
+
+        Personality {
+            ambition: blend(Facet::Ambition),
+            piety: blend(Facet::Piety),
+            cruelty: blend(Facet::Cruelty),
+            loyalty: blend(Facet::Loyalty),
+            lust: blend(Facet::Lust),
+        }
+    }
+
+    pub fn facet(&self, facet: Facet) -> f64 {
+        match facet {
+            Facet::Ambition => self.ambition,
+            Facet::Piety => self.piety,
+            Facet::Cruelty => self.cruelty,
+            Facet::Loyalty => self.loyalty,
+            Facet::Lust => self.lust,
+        }
+    }
+
+    /// How well two people get along, from 0 to 1. People like people like themselves.
+    pub fn compatibility(&self, other: &Personality) -> f64 {
+        let difference = (self.loyalty - other.loyalty).abs()
+            + (self.piety - other.piety).abs()
+            + (self.ambition - other.ambition).abs();
+        1.0 - difference / 3.0
+    }
+
+    /// The facet that most drives this person
+    pub fn dominant(&self) -> Facet {
+        [
+            Facet::Ambition,
+            Facet::Piety,
+            Facet::Cruelty,
+            Facet::Loyalty,
+            Facet::Lust,
+        ]
+        .into_iter()
+        .max_by(|a, b| self.facet(*a).total_cmp(&self.facet(*b)))
+        .unwrap()
+    }
+
+    /// How someone takes the death of a loved one
+    pub fn grief_reaction(&self) -> &'static str {
+        match self.dominant() {
+            Facet::Ambition => "eyes what was left behind",
+            Facet::Piety => "prays for their soul",
+            Facet::Cruelty => "sheds not a single tear",
+            Facet::Loyalty => "is inconsolable",
+            Facet::Lust => "drowns their sorrows",
+        }
+    }
+
+    /// How someone takes being spared by their house
+    pub fn cheat_death_reaction(&self) -> &'static str {
+        match self.dominant() {
+            Facet::Ambition => "takes it as a sign of their destiny",
+            Facet::Piety => "swears a lifetime of devotion",
+            Facet::Cruelty => "feels untouchable",
+            Facet::Loyalty => "vows to repay the debt",
+            Facet::Lust => "resolves to live every day to the fullest",
+        }
+    }
+}
+
+/// The facet each house encourages in those born under it
+fn house_favours(house: &MoonHouse) -> Facet {
+    use MoonHouse::{Dark, Death, Dream, Earth, Fire, Light, Storm, Water, Wild, Wind};
+    match house {
+        Fire | Storm => Facet::Ambition,
+        Light | Earth => Facet::Piety,
+        Dark | Death => Facet::Cruelty,
+        Water | Wind => Facet::Loyalty,
+        Dream | Wild => Facet::Lust,
+    }
+}
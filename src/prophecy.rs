@@ -0,0 +1,365 @@
+use std::fmt;
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+use rand::prelude::*;
+
+use crate::{
+    age::Age,
+    life::{Alive, DeathEvent},
+    marriage::{MarriageEvent, Spouse},
+    moon::{Moon, MoonHouse, MoonPhase},
+    people::Name,
+    religion::{Heretic, Priest},
+    reproduction::BirthEvent,
+    royalty::{Prestige, Royalty},
+    state::RunState,
+    succession::SuccessionEvent,
+};
+
+/// Chance a priest finds meaning in a new moon
+const NEW_MOON_READING_CHANCE: f64 = 0.1;
+/// Chance a priest finds meaning in a house rising, a much rarer sign
+const RISING_READING_CHANCE: f64 = 0.4;
+/// Only royals this old are foretold to wed
+const MARRIAGEABLE_AGE: i32 = 16;
+/// How long a prophecy has to come true
+const PROPHECY_DAYS: u32 = 360;
+const FULFILLED_PRESTIGE: i32 = 20;
+const FAILED_PRESTIGE: i32 = -5;
+
+/// A sign in the heavens
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Omen {
+    NewMoon,
+    Rising(MoonHouse),
+}
+
+impl fmt::Display for Omen {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Omen::NewMoon => write!(f, "the new moon"),
+            Omen::Rising(house) => write!(f, "the rise of House {house}"),
+        }
+    }
+}
+
+/// What a prophecy says will happen
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Foretelling {
+    Death(Entity),
+    /// A child born to them
+    Birth(Entity),
+    Marriage(Entity),
+    Ascendancy(MoonHouse),
+    NewReign,
+}
+
+/// A prediction waiting to come true, or not
+#[derive(Component, Debug)]
+pub struct Prophecy {
+    pub prophet: Entity,
+    pub omen: Omen,
+    pub foretelling: Foretelling,
+    /// Days left for it to come true
+    pub days: u32,
+}
+
+/// Someone known for their prophecies, and how many of them came true
+#[derive(Component, Debug, Default)]
+pub struct Seer {
+    pub fulfilled: u32,
+    pub failed: u32,
+}
+
+/// Something has been seen in the heavens
+#[derive(Event, Debug)]
+pub struct OmenEvent {
+    omen: Omen,
+}
+
+impl OmenEvent {
+    pub fn new(omen: Omen) -> Self {
+        OmenEvent { omen }
+    }
+
+    pub fn omen(&self) -> &Omen {
+        &self.omen
+    }
+}
+
+/// A prophecy came true, or its time ran out
+#[derive(Event, Debug)]
+pub struct ProphecyEvent {
+    prophet: Entity,
+    omen: Omen,
+    foretelling: Foretelling,
+    fulfilled: bool,
+}
+
+impl ProphecyEvent {
+    pub fn new(prophet: Entity, omen: Omen, foretelling: Foretelling, fulfilled: bool) -> Self {
+        ProphecyEvent {
+            prophet,
+            omen,
+            foretelling,
+            fulfilled,
+        }
+    }
+
+    pub fn prophet(&self) -> Entity {
+        self.prophet
+    }
+
+    pub fn omen(&self) -> &Omen {
+        &self.omen
+    }
+
+    pub fn foretelling(&self) -> &Foretelling {
+        &self.foretelling
+    }
+
+    pub fn is_fulfilled(&self) -> bool {
+        self.fulfilled
+    }
+}
+
+/// What has come to pass today, as far as prophecies go
+#[derive(SystemParam)]
+pub struct Happenings<'w, 's> {
+    deaths: EventReader<'w, 's, DeathEvent>,
+    births: EventReader<'w, 's, BirthEvent>,
+    marriages: EventReader<'w, 's, MarriageEvent>,
+    successions: EventReader<'w, 's, SuccessionEvent>,
+    omens: EventReader<'w, 's, OmenEvent>,
+}
+
+impl Happenings<'_, '_> {
+    pub fn read(&mut self) -> Vec<Foretelling> {
+        let died = self
+            .deaths
+            .read()
+            .map(|event| Foretelling::Death(event.dying()));
+        let born = self.births.read().flat_map(|event| {
+            [
+                Foretelling::Birth(event.mother()),
+                Foretelling::Birth(event.father()),
+            ]
+        });
+        let wed = self.marriages.read().flat_map(|event| {
+            [
+                Foretelling::Marriage(event.first()),
+                Foretelling::Marriage(event.second()),
+            ]
+        });
+        let crowned = self.successions.read().map(|_| Foretelling::NewReign);
+        let risen = self.omens.read().filter_map(|event| match event.omen() {
+            Omen::Rising(house) => Some(Foretelling::Ascendancy(house.clone())),
+            Omen::NewMoon => None,
+        });
+        died.chain(born)
+            .chain(wed)
+            .chain(crowned)
+            .chain(risen)
+            .collect()
+    }
+}
+
+/// What a prophecy says, e.g. "the death of Jack Allan"
+fn describe(foretelling: &Foretelling, query_names: &Query<&Name>) -> String {
+    let name = |person: &Entity| {
+        query_names.get(*person).map_or_else(
+            |_| "someone".into(),
+            |name| format!("{} {}", name.first, name.last),
+        )
+    };
+    match foretelling {
+        Foretelling::Death(person) => format!("the death of {}", name(person)),
+        Foretelling::Birth(person) => format!("a child born to {}", name(person)),
+        Foretelling::Marriage(person) => format!("the wedding of {}", name(person)),
+        Foretelling::Ascendancy(house) => format!("the rise of House {house}"),
+        Foretelling::NewReign => "a new reign".into(),
+    }
+}
+
+/// Watch for new moons and houses rising
+fn watch_the_skies(
+    mut last_house: Local<Option<MoonHouse>>,
+    query_moon: Query<&Moon>,
+    mut ev_omen: EventWriter<OmenEvent>,
+) {
+    let moon = query_moon.single();
+
+    if moon.phase == MoonPhase::New {
+        ev_omen.send(OmenEvent::new(Omen::NewMoon));
+    }
+    if last_house
+        .as_ref()
+        .is_some_and(|house| *house != moon.house)
+    {
+        ev_omen.send(OmenEvent::new(Omen::Rising(moon.house.clone())));
+    }
+    *last_house = Some(moon.house.clone());
+}
+
+/// Priests read the omens in the light of a house, the one rising or else their own, and
+/// prophesy about the royal family: Death and Dark foretell deaths, Earth and Water births, Light
+/// and Dream weddings, Storm and Fire a new reign, and Wind and Wild the rise of the priest's house
+fn read_omens(
+    mut commands: Commands,
+    mut ev_omen: EventReader<OmenEvent>,
+    query_priests: Query<(Entity, &Priest), (With<Alive>, Without<Heretic>)>,
+    query_royals: Query<(Entity, &Age, Option<&Spouse>), (With<Royalty>, With<Alive>)>,
+    query_prophecies: Query<&Prophecy>,
+    query_names: Query<&Name>,
+) {
+    let mut rng = thread_rng();
+
+    for event in ev_omen.read() {
+        let (omen, chance) = match event.omen() {
+            Omen::NewMoon => (Omen::NewMoon, NEW_MOON_READING_CHANCE),
+            Omen::Rising(house) => (Omen::Rising(house.clone()), RISING_READING_CHANCE),
+        };
+
+        for (prophet, priest) in &query_priests {
+            if !rng.gen_bool(chance)
+                || query_prophecies
+                    .iter()
+                    .any(|prophecy| prophecy.prophet == prophet)
+            {
+                continue;
+            }
+
+            let house = match &omen {
+                Omen::Rising(house) => house,
+                Omen::NewMoon => &priest.house,
+            };
+            let royals: Vec<(Entity, &Age, Option<&Spouse>)> = query_royals.iter().collect();
+            let foretelling = match house {
+                // the old are more likely to be marked for death
+                MoonHouse::Death | MoonHouse::Dark => royals
+                    .choose_weighted(&mut rng, |(_, age, _)| age.years().max(1))
+                    .ok()
+                    .map(|(royal, _, _)| Foretelling::Death(*royal)),
+                MoonHouse::Earth | MoonHouse::Water => royals
+                    .iter()
+                    .filter(|(_, _, spouse)| spouse.is_some())
+                    .choose(&mut rng)
+                    .map(|(royal, _, _)| Foretelling::Birth(*royal)),
+                MoonHouse::Light | MoonHouse::Dream => royals
+                    .iter()
+                    .filter(|(_, age, spouse)| spouse.is_none() && age.years() >= MARRIAGEABLE_AGE)
+                    .choose(&mut rng)
+                    .map(|(royal, _, _)| Foretelling::Marriage(*royal)),
+                MoonHouse::Storm | MoonHouse::Fire => Some(Foretelling::NewReign),
+                // no prophet earns renown for foretelling what's already come to pass
+                MoonHouse::Wind | MoonHouse::Wild => (omen != Omen::Rising(priest.house.clone()))
+                    .then(|| Foretelling::Ascendancy(priest.house.clone())),
+            };
+            let Some(foretelling) = foretelling else {
+                continue;
+            };
+
+            if let Ok(name) = query_names.get(prophet) {
+                info!(
+                    "{} {}, priest of House {}, reads {} and foretells {}",
+                    name.first,
+                    name.last,
+                    priest.house,
+                    omen,
+                    describe(&foretelling, &query_names)
+                );
+            }
+            commands.entity(prophet).insert_if_new(Seer::default());
+            commands.spawn(Prophecy {
+                prophet,
+                omen: omen.clone(),
+                foretelling,
+                days: PROPHECY_DAYS,
+            });
+        }
+    }
+}
+
+/// Prophecies come true when what they foretold happens, and fail if their time runs out first
+fn fulfil_prophecies(
+    mut commands: Commands,
+    mut happenings: Happenings,
+    mut query_prophecies: Query<(Entity, &mut Prophecy)>,
+    mut ev_prophecy: EventWriter<ProphecyEvent>,
+) {
+    let happened = happenings.read();
+
+    for (entity, mut prophecy) in &mut query_prophecies {
+        // only what happens after a prophecy is made can fulfil it
+        let made_today = prophecy.days == PROPHECY_DAYS;
+        let fulfilled = !made_today && happened.contains(&prophecy.foretelling);
+        prophecy.days = prophecy.days.saturating_sub(1);
+        if !fulfilled && prophecy.days > 0 {
+            continue;
+        }
+
+        commands.entity(entity).despawn();
+        ev_prophecy.send(ProphecyEvent::new(
+            prophecy.prophet,
+            prophecy.omen.clone(),
+            prophecy.foretelling.clone(),
+            fulfilled,
+        ));
+    }
+}
+
+/// Prophets who are proved right win renown, and those proved wrong lose a little
+fn judge_prophets(
+    mut ev_prophecy: EventReader<ProphecyEvent>,
+    mut query_prophets: Query<(&Name, &mut Prestige, &mut Seer)>,
+    query_names: Query<&Name>,
+) {
+    for event in ev_prophecy.read() {
+        let Ok((name, mut prestige, mut seer)) = query_prophets.get_mut(event.prophet()) else {
+            continue;
+        };
+        let what = describe(event.foretelling(), &query_names);
+
+        if event.is_fulfilled() {
+            seer.fulfilled += 1;
+            prestige.adjust(FULFILLED_PRESTIGE);
+            info!(
+                "{} {} foretold {} at {}, and it has come to pass. They have now been proved \
+                 right in {} of {} prophecies",
+                name.first,
+                name.last,
+                what,
+                event.omen(),
+                seer.fulfilled,
+                seer.fulfilled + seer.failed
+            );
+        } else {
+            seer.failed += 1;
+            prestige.adjust(FAILED_PRESTIGE);
+            debug!(
+                "{} {}'s prophecy of {} did not come to pass",
+                name.first, name.last, what
+            );
+        }
+    }
+}
+
+pub struct ProphecyPlugin;
+
+impl Plugin for ProphecyPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<OmenEvent>()
+            .add_event::<ProphecyEvent>()
+            .add_systems(
+                Update,
+                (
+                    watch_the_skies,
+                    read_omens,
+                    fulfil_prophecies,
+                    judge_prophets,
+                )
+                    .chain()
+                    .run_if(in_state(RunState::Running)),
+            );
+    }
+}
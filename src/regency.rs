@@ -0,0 +1,330 @@
+use bevy::prelude::*;
+use rand::prelude::*;
+
+use crate::{
+    life::Alive,
+    people::Name,
+    personality::Personality,
+    relationships::OpinionEvent,
+    royalty::{Royalty, Title},
+    state::RunState,
+    succession::{Claimants, SuccessionLaw, SuccessionRules},
+};
+
+/// Most a regent with the highest ambition will refuse to give up power
+const MAX_REFUSAL_CHANCE: f64 = 0.5;
+/// Chance each day that a regent who refused to step down finally gives in
+const DAILY_RELENT_CHANCE: f64 = 1.0 / 365.0;
+/// How much a monarch resents being kept from their throne
+const USURPED_OPINION: i32 = -40;
+
+/// Who picks the regent when a child takes the throne
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegentChoice {
+    /// A surviving parent, else the next adult in line
+    Law,
+    /// The royals vote among the adults of the family
+    Council,
+}
+
+impl RegentChoice {
+    /// A family that picks its monarch picks the regent too
+    pub fn for_law(law: SuccessionLaw) -> Self {
+        match law {
+            SuccessionLaw::Elective | SuccessionLaw::Tanistry => RegentChoice::Council,
+            SuccessionLaw::MalePreferencePrimogeniture
+            | SuccessionLaw::AbsolutePrimogeniture
+            | SuccessionLaw::Seniority => RegentChoice::Law,
+        }
+    }
+}
+
+#[derive(Resource, Debug)]
+pub struct RegencyRules {
+    /// Years a monarch must reach to rule in their own right
+    pub age_of_majority: i32,
+}
+
+impl Default for RegencyRules {
+    fn default() -> Self {
+        RegencyRules {
+            age_of_majority: 16,
+        }
+    }
+}
+
+/// Rules in the name of a monarch too young to rule themselves
+#[derive(Component, Debug)]
+pub struct Regent {
+    pub ward: Entity,
+    /// Refused to step down when the ward came of age
+    pub defiant: bool,
+}
+
+/// Who actually rules for `monarch`: their regent if they have one, else the monarch themselves
+pub fn acting_ruler<'a>(
+    monarch: Entity,
+    regents: impl IntoIterator<Item = (Entity, &'a Regent)>,
+) -> Entity {
+    regents
+        .into_iter()
+        .find(|(_, regency)| regency.ward == monarch)
+        .map_or(monarch, |(regent, _)| regent)
+}
+
+/// A monarch with a regent has reached the age of majority
+#[derive(Event, Debug)]
+pub struct ComingOfAgeEvent {
+    monarch: Entity,
+    regent: Entity,
+}
+
+impl ComingOfAgeEvent {
+    pub fn new(monarch: Entity, regent: Entity) -> Self {
+        ComingOfAgeEvent { monarch, regent }
+    }
+
+    pub fn monarch(&self) -> Entity {
+        self.monarch
+    }
+
+    pub fn regent(&self) -> Entity {
+        self.regent
+    }
+}
+
+/// Pick someone to rule for an underage monarch
+fn choose_regent(
+    monarch: Entity,
+    choice: RegentChoice,
+    law: SuccessionLaw,
+    claimants: &Claimants,
+) -> Option<Entity> {
+    match choice {
+        RegentChoice::Law => claimants
+            .kinship()
+            .parents(monarch)
+            .into_iter()
+            .find(|parent| claimants.can_claim(*parent) && claimants.is_adult(*parent))
+            .or_else(|| {
+                let male_preference = law == SuccessionLaw::MalePreferencePrimogeniture;
+                claimants
+                    .line_of_succession(monarch, male_preference)
+                    .into_iter()
+                    .find(|person| claimants.is_adult(*person))
+            }),
+        RegentChoice::Council => {
+            let mut candidates: Vec<Entity> = claimants
+                .dynasty(monarch)
+                .into_iter()
+                .chain(claimants.kinship().parents(monarch))
+                .filter(|person| claimants.can_claim(*person) && claimants.is_adult(*person))
+                .collect();
+            candidates.sort();
+            candidates.dedup();
+            claimants.vote(monarch, &candidates)
+        }
+    }
+}
+
+/// Make sure every underage monarch has a living regent, e.g. after a succession or a regent's death
+fn appoint_regents(
+    mut commands: Commands,
+    rules: Res<RegencyRules>,
+    succession_rules: Res<SuccessionRules>,
+    claimants: Claimants,
+    query_monarchs: Query<(Entity, &Name, &Royalty), With<Alive>>,
+    query_regents: Query<&Regent, With<Alive>>,
+    query_names: Query<&Name>,
+) {
+    for (monarch, name, royalty) in &query_monarchs {
+        if royalty.title != Title::Monarch
+            || claimants.is_of_age(monarch, rules.age_of_majority)
+            || query_regents.iter().any(|regent| regent.ward == monarch)
+        {
+            continue;
+        }
+
+        let choice = RegentChoice::for_law(succession_rules.law);
+        let Some(regent) = choose_regent(monarch, choice, succession_rules.law, &claimants) else {
+            continue;
+        };
+
+        commands.entity(regent).insert(Regent {
+            ward: monarch,
+            defiant: false,
+        });
+        if let Ok(regent_name) = query_names.get(regent) {
+            info!(
+                "{} {} is named regent by {:?} to rule for the young {} {}",
+                regent_name.first, regent_name.last, choice, name.first, name.last
+            );
+        }
+    }
+}
+
+/// Spot the day a ward reaches the age of majority
+fn come_of_age(
+    rules: Res<RegencyRules>,
+    claimants: Claimants,
+    query: Query<(Entity, &Regent), With<Alive>>,
+    mut ev_coming_of_age: EventWriter<ComingOfAgeEvent>,
+) {
+    for (regent, regency) in &query {
+        if !regency.defiant && claimants.is_of_age(regency.ward, rules.age_of_majority) {
+            ev_coming_of_age.send(ComingOfAgeEvent::new(regency.ward, regent));
+        }
+    }
+}
+
+/// The regent hands over power, unless they're ambitious enough to cling on to it
+fn end_regency(
+    mut commands: Commands,
+    mut ev_coming_of_age: EventReader<ComingOfAgeEvent>,
+    mut ev_opinion: EventWriter<OpinionEvent>,
+    mut query: Query<(&Name, &Personality, &mut Regent)>,
+    query_names: Query<&Name>,
+) {
+    let mut rng = thread_rng();
+
+    for event in ev_coming_of_age.read() {
+        let Ok((regent_name, personality, mut regency)) = query.get_mut(event.regent()) else {
+            continue;
+        };
+        let Ok(name) = query_names.get(event.monarch()) else {
+            continue;
+        };
+
+        let refusal_chance = (personality.ambition * MAX_REFUSAL_CHANCE).clamp(0.0, 1.0);
+        if rng.gen_bool(refusal_chance) {
+            regency.defiant = true;
+            ev_opinion.send(OpinionEvent::new(
+                event.monarch(),
+                event.regent(),
+                USURPED_OPINION,
+                "refused to give up the regency",
+            ));
+            warn!(
+                "{} {} has come of age but regent {} {} refuses to step down",
+                name.first, name.last, regent_name.first, regent_name.last
+            );
+        } else {
+            commands.entity(event.regent()).remove::<Regent>();
+            info!(
+                "{} {} has come of age and rules in their own right, {} {}'s regency is over",
+                name.first, name.last, regent_name.first, regent_name.last
+            );
+        }
+    }
+}
+
+/// Regencies end when the ward loses the throne, the regent dies, or a defiant regent gives in
+fn dissolve_regencies(
+    mut commands: Commands,
+    query: Query<(Entity, &Name, &Regent, Has<Alive>)>,
+    query_monarchs: Query<&Royalty, With<Alive>>,
+) {
+    let mut rng = thread_rng();
+
+    for (regent, name, regency, alive) in &query {
+        let ward_reigns = query_monarchs
+            .get(regency.ward)
+            .is_ok_and(|royalty| royalty.title == Title::Monarch);
+        if alive && ward_reigns && !(regency.defiant && rng.gen_bool(DAILY_RELENT_CHANCE)) {
+            continue;
+        }
+
+        commands.entity(regent).remove::<Regent>();
+        if regency.defiant && alive && ward_reigns {
+            info!(
+                "{} {} finally gives up the regency they held on to",
+                name.first, name.last
+            );
+        }
+    }
+}
+
+pub struct RegencyPlugin;
+
+impl Plugin for RegencyPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RegencyRules>()
+            .add_event::<ComingOfAgeEvent>()
+            .add_systems(
+                Update,
+                (
+                    dissolve_regencies,
+                    appoint_regents,
+                    come_of_age,
+                    end_regency,
+                )
+                    .chain()
+                    .run_if(in_state(RunState::Running)),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{ecs::system::RunSystemOnce, utils::HashSet};
+
+    use super::*;
+    use crate::{
+        age::Age,
+        calendar::YEAR_LENGTH,
+        people::{Children, Parents},
+        relationships::Relationships,
+        sex::Gender,
+        traits::Traits,
+    };
+
+    fn spawn_person(world: &mut World, parents: &[Entity], years: i32, ambition: f64) -> Entity {
+        world
+            .spawn((
+                Parents::new(parents.iter().copied().collect::<HashSet<_>>()),
+                Age::new(years * YEAR_LENGTH.cast_signed()),
+                Gender::Woman,
+                Personality {
+                    ambition,
+                    piety: 0.5,
+                    cruelty: 0.5,
+                    loyalty: 0.5,
+                    lust: 0.5,
+                },
+                Traits {
+                    genes: default(),
+                    attributes: default(),
+                },
+                Alive,
+            ))
+            .id()
+    }
+
+    fn regent_for(
+        In((monarch, law)): In<(Entity, SuccessionLaw)>,
+        claimants: Claimants,
+    ) -> Option<Entity> {
+        choose_regent(monarch, RegentChoice::for_law(law), law, &claimants)
+    }
+
+    #[test]
+    fn council_can_pass_over_the_mother() {
+        let mut world = World::new();
+        world.init_resource::<Relationships>();
+        let grandmother = spawn_person(&mut world, &[], 60, 0.5);
+        let mother = spawn_person(&mut world, &[grandmother], 30, 0.0);
+        let aunt = spawn_person(&mut world, &[grandmother], 28, 1.0);
+        let monarch = spawn_person(&mut world, &[mother], 5, 0.5);
+        world.flush();
+
+        let by_law = world
+            .run_system_once_with((monarch, SuccessionLaw::AbsolutePrimogeniture), regent_for)
+            .unwrap();
+        assert_eq!(by_law, Some(mother));
+
+        let by_council = world
+            .run_system_once_with((monarch, SuccessionLaw::Elective), regent_for)
+            .unwrap();
+        assert_eq!(by_council, Some(aunt));
+    }
+}
@@ -0,0 +1,432 @@
+use std::fmt::Write as _;
+
+use bevy::{prelude::*, utils::HashMap};
+use rand::prelude::*;
+
+use crate::{
+    age::Age,
+    calendar::Calendar,
+    kinship::Kinship,
+    life::{distinct_deaths, Alive, DeathEvent},
+    marriage::{DivorceEvent, MarriageEvent, Spouse},
+    people::{AssignedMoonHouse, Name, Parents},
+    personality::Personality,
+    state::RunState,
+};
+
+const OPINION_LIMIT: i32 = 100;
+/// What people think of their family with nothing else going on
+const KIN_OPINION: i32 = 30;
+/// People born under the same house get on a little better
+const SHARED_HOUSE_OPINION: i32 = 10;
+const MARRIAGE_OPINION: i32 = 20;
+const DIVORCE_OPINION: i32 = -40;
+const AFFAIR_OPINION: i32 = 20;
+const FRIEND_THRESHOLD: i32 = 60;
+const ENEMY_THRESHOLD: i32 = -60;
+const RIVAL_THRESHOLD: i32 = -30;
+/// How ambitious both people must be to see each other as rivals
+const RIVAL_AMBITION: f64 = 0.6;
+const ADULT_AGE: i32 = 16;
+/// How much older than a youngster someone must be to mentor them
+const MENTOR_AGE_GAP: i32 = 10;
+/// Chance each day that an opinion drifts back towards where it started. Daily interactions move
+/// opinions every day, so drift has to keep pace or everyone ends up a friend or an enemy.
+const DAILY_DRIFT_CHANCE: f64 = 0.2;
+/// Strong feelings fade faster, each drift closes this fraction of the gap
+const DRIFT_DIVISOR: i32 = 10;
+const EXPORT_PATH: &str = "relationships.dot";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelationKind {
+    Friend,
+    Rival,
+    Lover,
+    Mentor,
+    Enemy,
+}
+
+impl RelationKind {
+    fn colour(self) -> &'static str {
+        match self {
+            RelationKind::Friend => "green",
+            RelationKind::Rival => "orange",
+            RelationKind::Lover => "red",
+            RelationKind::Mentor => "blue",
+            RelationKind::Enemy => "black",
+        }
+    }
+}
+
+/// How one person sees another
+#[derive(Debug, Clone)]
+pub struct Relationship {
+    /// From -100 (loathing) to 100 (adoration)
+    pub opinion: i32,
+    /// Where the opinion drifts back to, from kinship and a shared moon house
+    pub baseline: i32,
+    pub kind: Option<RelationKind>,
+}
+
+impl Relationship {
+    fn new(baseline: i32) -> Self {
+        Relationship {
+            opinion: baseline,
+            baseline,
+            kind: None,
+        }
+    }
+
+    /// Settle what kind of relationship this is after the opinion changes, returning whether it changed
+    fn reclassify(&mut self, rivals: bool, mentor: bool) -> bool {
+        use RelationKind::{Enemy, Friend, Lover, Mentor, Rival};
+        let kind = match self.kind {
+            Some(Lover) if self.opinion > ENEMY_THRESHOLD => Some(Lover),
+            _ if self.opinion <= ENEMY_THRESHOLD => Some(Enemy),
+            _ if self.opinion <= RIVAL_THRESHOLD && rivals => Some(Rival),
+            _ if self.opinion >= FRIEND_THRESHOLD => Some(if mentor { Mentor } else { Friend }),
+            // feelings linger a while after they've cooled
+            Some(Friend | Mentor) if self.opinion >= FRIEND_THRESHOLD / 2 => self.kind,
+            Some(Enemy | Rival) if self.opinion < 0 => self.kind,
+            _ => None,
+        };
+
+        let changed = kind != self.kind;
+        self.kind = kind;
+        changed
+    }
+}
+
+/// Who thinks what of whom. Edges are directed, A can adore B while B can't stand A.
+#[derive(Resource, Debug, Default)]
+pub struct Relationships {
+    edges: HashMap<Entity, HashMap<Entity, Relationship>>,
+    /// What the living thought of the dead when they died, kept for remembering them by
+    remembered: HashMap<Entity, HashMap<Entity, Relationship>>,
+}
+
+impl Relationships {
+    pub fn get(&self, from: Entity, to: Entity) -> Option<&Relationship> {
+        self.edges
+            .get(&from)
+            .and_then(|edges| edges.get(&to))
+            .or_else(|| self.remembered.get(&from)?.get(&to))
+    }
+
+    /// What `from` thinks of `to`, 0 if they've never met
+    pub fn opinion(&self, from: Entity, to: Entity) -> i32 {
+        self.get(from, to)
+            .map_or(0, |relationship| relationship.opinion)
+    }
+
+    /// How `from` sees `to`, e.g. `Mentor` means `to` is `from`'s mentor
+    pub fn kind(&self, from: Entity, to: Entity) -> Option<RelationKind> {
+        self.get(from, to)?.kind
+    }
+
+    /// Everyone `from` has an opinion of
+    pub fn known_by(&self, from: Entity) -> impl Iterator<Item = (Entity, &Relationship)> {
+        self.edges
+            .get(&from)
+            .into_iter()
+            .flat_map(|edges| edges.iter().map(|(to, relationship)| (*to, relationship)))
+    }
+
+    /// Everyone `from` sees as a particular kind of relation, e.g. all their enemies
+    pub fn with_kind(&self, from: Entity, kind: RelationKind) -> impl Iterator<Item = Entity> + '_ {
+        self.known_by(from)
+            .filter(move |(_, relationship)| relationship.kind == Some(kind))
+            .map(|(to, _)| to)
+    }
+
+    /// Two people start an affair, whatever they were to each other before
+    pub fn make_lovers(&mut self, first: Entity, second: Entity) {
+        for (from, to) in [(first, second), (second, first)] {
+            let relationship = self.entry(from, to, 0);
+            relationship.opinion = (relationship.opinion + AFFAIR_OPINION).min(OPINION_LIMIT);
+            relationship.kind = Some(RelationKind::Lover);
+        }
+    }
+
+    /// Someone has died: drop what they thought of everyone, and set aside what everyone thought
+    /// of them so it no longer drifts or counts as a living tie
+    pub fn forget(&mut self, dead: Entity) {
+        self.edges.remove(&dead);
+        self.remembered.remove(&dead);
+        for (from, edges) in &mut self.edges {
+            if let Some(mut relationship) = edges.remove(&dead) {
+                relationship.kind = None;
+                self.remembered
+                    .entry(*from)
+                    .or_default()
+                    .insert(dead, relationship);
+            }
+        }
+    }
+
+    /// Someone no longer exists at all, so nobody thinks anything of them, even in memory
+    pub fn erase(&mut self, person: Entity) {
+        self.edges.remove(&person);
+        self.remembered.remove(&person);
+        for edges in self.edges.values_mut().chain(self.remembered.values_mut()) {
+            edges.remove(&person);
+        }
+    }
+
+    fn entry(&mut self, from: Entity, to: Entity, baseline: i32) -> &mut Relationship {
+        self.edges
+            .entry(from)
+            .or_default()
+            .entry(to)
+            .or_insert_with(|| Relationship::new(baseline))
+    }
+
+    /// Graphviz for everyone `name` knows about, draw it with e.g. `dot -Tsvg`
+    pub fn to_dot(&self, name: impl Fn(Entity) -> Option<String>) -> String {
+        let mut dot = String::from("digraph relationships {\n");
+        for (from, edges) in &self.edges {
+            let Some(from_name) = name(*from) else {
+                continue;
+            };
+            let _ = writeln!(dot, "  \"{}\" [label=\"{from_name}\"];", from.to_bits());
+
+            for (to, relationship) in edges {
+                if name(*to).is_none() {
+                    continue;
+                }
+                let colour = relationship.kind.map_or("grey", RelationKind::colour);
+                let _ = writeln!(
+                    dot,
+                    "  \"{}\" -> \"{}\" [label=\"{}\", color=\"{colour}\"];",
+                    from.to_bits(),
+                    to.to_bits(),
+                    relationship.opinion
+                );
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+fn baseline(kin: bool, shared_house: bool) -> i32 {
+    let mut baseline = 0;
+    if kin {
+        baseline += KIN_OPINION;
+    }
+    if shared_house {
+        baseline += SHARED_HOUSE_OPINION;
+    }
+    baseline
+}
+
+/// Something happened that changed what one person thinks of another
+#[derive(Event, Debug)]
+pub struct OpinionEvent {
+    from: Entity,
+    to: Entity,
+    change: i32,
+    reason: &'static str,
+}
+
+impl OpinionEvent {
+    pub fn new(from: Entity, to: Entity, change: i32, reason: &'static str) -> Self {
+        OpinionEvent {
+            from,
+            to,
+            change,
+            reason,
+        }
+    }
+}
+
+/// Make sure everyone has an opinion of their family, grounded in kinship
+fn acquaint_kin(
+    mut relationships: ResMut<Relationships>,
+    kinship: Kinship,
+    query_people: Query<(Entity, &AssignedMoonHouse, Option<&Spouse>), With<Alive>>,
+    query_houses: Query<&AssignedMoonHouse, With<Alive>>,
+) {
+    for (person, house, spouse) in &query_people {
+        for relative in kinship.close_kin(person) {
+            let Ok(relative_house) = query_houses.get(relative) else {
+                continue;
+            };
+            let baseline = baseline(true, house.house == relative_house.house);
+            relationships.entry(person, relative, baseline).baseline = baseline;
+        }
+
+        // couples who married before anyone was keeping track
+        if let Some(spouse) = spouse {
+            let relationship = relationships.entry(person, spouse.entity, 0);
+            if relationship.kind.is_none() {
+                relationship.kind = Some(RelationKind::Lover);
+            }
+        }
+    }
+}
+
+fn handle_opinion_change(
+    mut relationships: ResMut<Relationships>,
+    mut ev_opinion: EventReader<OpinionEvent>,
+    query: Query<(&Name, &AssignedMoonHouse, &Personality, &Age, &Parents), With<Alive>>,
+) {
+    for event in ev_opinion.read() {
+        let Ok([from, to]) = query.get_many([event.from, event.to]) else {
+            continue;
+        };
+        let (from_name, from_house, from_personality, from_age, from_parents) = from;
+        let (to_name, to_house, to_personality, to_age, _) = to;
+
+        let relationship = relationships.entry(
+            event.from,
+            event.to,
+            baseline(false, from_house.house == to_house.house),
+        );
+        relationship.opinion =
+            (relationship.opinion + event.change).clamp(-OPINION_LIMIT, OPINION_LIMIT);
+        debug!(
+            "{} {} now thinks {} of {} {}: {}",
+            from_name.first,
+            from_name.last,
+            relationship.opinion,
+            to_name.first,
+            to_name.last,
+            event.reason
+        );
+
+        let rivals = from_personality.ambition >= RIVAL_AMBITION
+            && to_personality.ambition >= RIVAL_AMBITION;
+        let mentor = from_age.years() < ADULT_AGE
+            && to_age.years() >= from_age.years() + MENTOR_AGE_GAP
+            && !from_parents.contains(&event.to);
+
+        if !relationship.reclassify(rivals, mentor) {
+            continue;
+        }
+        if let Some(kind) = relationship.kind {
+            info!(
+                "{} {} now sees {} {} as their {:?}",
+                from_name.first, from_name.last, to_name.first, to_name.last, kind
+            );
+        } else {
+            info!(
+                "{} {} and {} {} have drifted apart",
+                from_name.first, from_name.last, to_name.first, to_name.last
+            );
+        }
+    }
+}
+
+/// Newlyweds are lovers, divorcees much less so
+fn handle_partnership_change(
+    mut relationships: ResMut<Relationships>,
+    mut ev_marriage: EventReader<MarriageEvent>,
+    mut ev_divorce: EventReader<DivorceEvent>,
+) {
+    for event in ev_marriage.read() {
+        for (from, to) in [
+            (event.first(), event.second()),
+            (event.second(), event.first()),
+        ] {
+            let relationship = relationships.entry(from, to, 0);
+            relationship.opinion = (relationship.opinion + MARRIAGE_OPINION).min(OPINION_LIMIT);
+            relationship.kind = Some(RelationKind::Lover);
+        }
+    }
+
+    for event in ev_divorce.read() {
+        for (from, to) in [
+            (event.first(), event.second()),
+            (event.second(), event.first()),
+        ] {
+            let relationship = relationships.entry(from, to, 0);
+            relationship.opinion = (relationship.opinion + DIVORCE_OPINION).max(-OPINION_LIMIT);
+            relationship.kind = None;
+            // they're not family any more
+            if relationship.baseline >= KIN_OPINION {
+                relationship.baseline -= KIN_OPINION;
+            }
+            relationship.reclassify(false, false);
+        }
+    }
+}
+
+/// The dead stop having opinions, and stop being lovers, friends or rivals to anyone
+fn forget_the_dead(
+    mut relationships: ResMut<Relationships>,
+    mut ev_death: EventReader<DeathEvent>,
+) {
+    for event in distinct_deaths(&mut ev_death) {
+        relationships.forget(event.dying());
+    }
+}
+
+/// With nothing else going on, opinions slowly settle back to where they started
+fn drift_opinions(mut relationships: ResMut<Relationships>) {
+    let mut rng = thread_rng();
+
+    for relationship in relationships
+        .edges
+        .values_mut()
+        .flat_map(HashMap::values_mut)
+    {
+        if relationship.opinion == relationship.baseline || !rng.gen_bool(DAILY_DRIFT_CHANCE) {
+            continue;
+        }
+        let gap = relationship.baseline - relationship.opinion;
+        relationship.opinion += if gap.abs() < DRIFT_DIVISOR {
+            gap.signum()
+        } else {
+            gap / DRIFT_DIVISOR
+        };
+        let rivals = relationship.kind == Some(RelationKind::Rival);
+        let mentor = relationship.kind == Some(RelationKind::Mentor);
+        relationship.reclassify(rivals, mentor);
+    }
+}
+
+/// Write the relationship graph out at the start of each year
+fn export_relationships(
+    relationships: Res<Relationships>,
+    query_calendar: Query<&Calendar>,
+    query_names: Query<&Name, With<Alive>>,
+) {
+    let calendar = query_calendar.single();
+    if calendar.year_day() != 1 {
+        return;
+    }
+
+    let dot = relationships.to_dot(|person| {
+        query_names
+            .get(person)
+            .ok()
+            .map(|name| format!("{} {}", name.first, name.last))
+    });
+    match std::fs::write(EXPORT_PATH, dot) {
+        Ok(()) => debug!("Exported relationships to {}", EXPORT_PATH),
+        Err(error) => warn!("Couldn't export relationships: {}", error),
+    }
+}
+
+pub struct RelationshipPlugin;
+
+impl Plugin for RelationshipPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Relationships>()
+            .add_event::<OpinionEvent>()
+            .add_systems(
+                Update,
+                (
+                    acquaint_kin,
+                    handle_partnership_change,
+                    handle_opinion_change,
+                    forget_the_dead,
+                    drift_opinions,
+                    export_relationships,
+                )
+                    .chain()
+                    .run_if(in_state(RunState::Running)),
+            );
+    }
+}
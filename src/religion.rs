@@ -0,0 +1,400 @@
+use bevy::prelude::*;
+use rand::prelude::*;
+
+use crate::{
+    age::Age,
+    calendar::Calendar,
+    crime::{CrimeEvent, CrimeKind, Exiled, Imprisoned, VerdictEvent},
+    kinship::Kinship,
+    life::{Alive, CheatDeathEvent},
+    memories::{Memories, MemoryKind},
+    moon::{Moon, MoonHouse},
+    people::{AssignedMoonHouse, Name},
+    personality::Personality,
+    royalty::Royalty,
+    state::RunState,
+};
+
+/// High rites are held at every temple on the last day of each décade
+const DECADE_LENGTH: u32 = 10;
+/// Old enough to serve a temple
+const PRIESTLY_AGE: i32 = 20;
+/// How far fervour drifts back towards someone's piety each day
+const FERVOUR_DRIFT: f64 = 0.002;
+/// Fervour gained from praying on a day the house they worship is ascendant, for the most pious
+const PRAYER_FERVOUR: f64 = 0.005;
+/// Fervour gained from attending a high rite
+const RITE_FERVOUR: f64 = 0.05;
+/// A priest leading the rite counts as this many offerings
+const PRIESTLY_OFFERINGS: u32 = 3;
+/// Offerings it takes to sway the heavens a little towards a house
+const OFFERINGS_PER_FAVOUR: u32 = 10;
+/// Chance each décade someone converts to the house of a more devout spouse or parent
+const CONVERSION_CHANCE: f64 = 0.05;
+/// How much more devout someone has to be to win a convert
+const CONVERSION_MARGIN: f64 = 0.3;
+/// Fervour of someone who was spared by a house
+const SPARED_FERVOUR: f64 = 1.0;
+/// People with less fervour than this may renounce the houses
+const HERESY_FERVOUR: f64 = 0.25;
+/// Chance each décade someone faithless renounces the houses, for the most ambitious and impious
+const HERESY_CHANCE: f64 = 0.05;
+/// Chance each décade a heretic sways one of their kin
+const HERESY_SPREAD_CHANCE: f64 = 0.1;
+/// Kin this devout won't be swayed by a heretic
+const STEADFAST_FERVOUR: f64 = 0.3;
+/// Chance each décade a priest hears of a heretic and denounces them
+const DENOUNCE_CHANCE: f64 = 0.05;
+const HERESY_EVIDENCE: f64 = 0.6;
+/// Fervour of a heretic made to recant
+const RECANTED_FERVOUR: f64 = 0.3;
+const CONVERSION_WEIGHT: f64 = 0.4;
+
+/// The house someone worships, and how fervently
+#[derive(Component, Debug)]
+pub struct Devotion {
+    pub house: MoonHouse,
+    /// From 0 to 1
+    pub fervour: f64,
+}
+
+/// Has renounced the High Houses
+#[derive(Component, Debug)]
+pub struct Heretic;
+
+/// Serves at the temple of a house
+#[derive(Component, Debug)]
+pub struct Priest {
+    pub house: MoonHouse,
+}
+
+/// Where a house is worshipped
+#[derive(Component, Debug)]
+pub struct Temple {
+    pub house: MoonHouse,
+    pub priest: Option<Entity>,
+    /// Offerings not yet enough to sway the heavens
+    pub offerings: u32,
+}
+
+fn is_decade_end(calendar: &Calendar) -> bool {
+    calendar.date().month_day.is_multiple_of(DECADE_LENGTH)
+}
+
+/// One temple for each of the High Houses
+fn found_temples(mut commands: Commands) {
+    for house in MoonHouse::ALL {
+        commands.spawn(Temple {
+            house,
+            priest: None,
+            offerings: 0,
+        });
+    }
+}
+
+/// Everyone starts out worshipping the house they were born under, as fervently as they're pious
+fn kindle_faith(
+    mut commands: Commands,
+    query: Query<(Entity, &AssignedMoonHouse, &Personality), Without<Devotion>>,
+) {
+    for (person, house, personality) in &query {
+        commands.entity(person).insert(Devotion {
+            house: house.house.clone(),
+            fervour: personality.piety,
+        });
+    }
+}
+
+/// Each day the faithful pray, more fervently when their house is ascendant, and otherwise
+/// settle back towards their natural piety
+fn daily_prayers(
+    query_moon: Query<&Moon>,
+    mut query: Query<(&mut Devotion, &Personality), (With<Alive>, Without<Heretic>)>,
+) {
+    let moon = query_moon.single();
+
+    for (mut devotion, personality) in &mut query {
+        devotion.fervour += (personality.piety - devotion.fervour) * FERVOUR_DRIFT;
+        if devotion.house == moon.house {
+            devotion.fervour += PRAYER_FERVOUR * personality.piety;
+        }
+        devotion.fervour = devotion.fervour.clamp(0.0, 1.0);
+    }
+}
+
+/// At the end of each décade every temple holds a high rite. The devout come, their faith is
+/// strengthened, and their offerings sway the heavens towards their house.
+fn hold_rites(
+    query_calendar: Query<&Calendar>,
+    mut query_moon: Query<&mut Moon>,
+    mut query_temples: Query<&mut Temple>,
+    mut query_faithful: Query<
+        (Entity, &mut Devotion),
+        (
+            With<Alive>,
+            Without<Heretic>,
+            Without<Imprisoned>,
+            Without<Exiled>,
+        ),
+    >,
+) {
+    if !is_decade_end(query_calendar.single()) {
+        return;
+    }
+    let mut rng = thread_rng();
+    let mut moon = query_moon.single_mut();
+
+    for mut temple in &mut query_temples {
+        let mut worshippers = 0;
+        for (_, mut devotion) in &mut query_faithful {
+            if devotion.house == temple.house && rng.gen_bool(devotion.fervour) {
+                devotion.fervour = (devotion.fervour + RITE_FERVOUR).min(1.0);
+                worshippers += 1;
+            }
+        }
+        if worshippers == 0 {
+            continue;
+        }
+
+        let led = temple
+            .priest
+            .is_some_and(|priest| query_faithful.contains(priest));
+        temple.offerings += worshippers + if led { PRIESTLY_OFFERINGS } else { 0 };
+        let favour = temple.offerings / OFFERINGS_PER_FAVOUR;
+        temple.offerings %= OFFERINGS_PER_FAVOUR;
+        moon.favour(&temple.house, favour);
+        debug!(
+            "{} worshippers make offerings at the Temple of {}",
+            worshippers, temple.house
+        );
+    }
+}
+
+/// A temple without a priest takes the most devout adult of its house into its service, royals
+/// have a realm to serve instead
+fn appoint_priests(
+    mut commands: Commands,
+    query_calendar: Query<&Calendar>,
+    mut query_temples: Query<&mut Temple>,
+    query_faithful: Query<
+        (Entity, &Name, &Devotion, &Age),
+        (
+            With<Alive>,
+            Without<Heretic>,
+            Without<Priest>,
+            Without<Royalty>,
+            Without<Exiled>,
+        ),
+    >,
+    query_priests: Query<(&Priest, &Devotion), (With<Alive>, Without<Heretic>)>,
+) {
+    if !is_decade_end(query_calendar.single()) {
+        return;
+    }
+
+    for mut temple in &mut query_temples {
+        // a priest who has died, strayed or turned to another house is replaced
+        let serving = temple.priest.is_some_and(|priest| {
+            query_priests
+                .get(priest)
+                .is_ok_and(|(priest, devotion)| devotion.house == priest.house)
+        });
+        if serving {
+            continue;
+        }
+        let Some((person, name, _, _)) = query_faithful
+            .iter()
+            .filter(|(_, _, devotion, age)| {
+                devotion.house == temple.house && age.years() >= PRIESTLY_AGE
+            })
+            .max_by(|(_, _, a, _), (_, _, b, _)| a.fervour.total_cmp(&b.fervour))
+        else {
+            continue;
+        };
+
+        if let Some(former) = temple.priest.replace(person) {
+            commands.entity(former).remove::<Priest>();
+        }
+        commands.entity(person).insert(Priest {
+            house: temple.house.clone(),
+        });
+        info!(
+            "{} {} becomes priest of the Temple of {}",
+            name.first, name.last, temple.house
+        );
+    }
+}
+
+/// People turn to the house that spared them, or to the house of a more devout spouse or parent
+fn convert_faithful(
+    mut ev_cheat_death: EventReader<CheatDeathEvent>,
+    kinship: Kinship,
+    query_calendar: Query<&Calendar>,
+    mut query: Query<
+        (Entity, &Name, &mut Devotion, &mut Memories),
+        (With<Alive>, Without<Heretic>),
+    >,
+) {
+    let calendar = query_calendar.single();
+    let date = calendar.date();
+    let mut rng = thread_rng();
+
+    let mut conversions: Vec<(Entity, MoonHouse, f64)> = ev_cheat_death
+        .read()
+        .map(|event| (event.cheater(), event.house().clone(), SPARED_FERVOUR))
+        .collect();
+
+    if is_decade_end(calendar) {
+        for (person, _, devotion, _) in &query {
+            let swayed_by = kinship
+                .spouse(person)
+                .into_iter()
+                .chain(kinship.parents(person))
+                .filter_map(|other| query.get(other).ok())
+                .filter(|(_, _, other, _)| {
+                    other.house != devotion.house
+                        && other.fervour >= devotion.fervour + CONVERSION_MARGIN
+                })
+                .max_by(|(_, _, a, _), (_, _, b, _)| a.fervour.total_cmp(&b.fervour));
+            if let Some((_, _, other, _)) = swayed_by {
+                if rng.gen_bool(CONVERSION_CHANCE) {
+                    // converts take on some of the zeal of whoever won them over
+                    let fervour = f64::midpoint(devotion.fervour, other.fervour);
+                    conversions.push((person, other.house.clone(), fervour));
+                }
+            }
+        }
+    }
+
+    for (person, house, fervour) in conversions {
+        let Ok((_, name, mut devotion, mut memories)) = query.get_mut(person) else {
+            continue;
+        };
+        if devotion.house == house {
+            devotion.fervour = devotion.fervour.max(fervour);
+            continue;
+        }
+        info!(
+            "{} {} turns from House {} to House {}",
+            name.first, name.last, devotion.house, house
+        );
+        devotion.house = house.clone();
+        devotion.fervour = fervour;
+        memories.remember(
+            MemoryKind::Converted { house },
+            None,
+            date,
+            CONVERSION_WEIGHT,
+        );
+    }
+}
+
+/// The faithless may renounce the houses altogether, and heretics lead their kin astray
+fn spread_heresy(
+    mut commands: Commands,
+    kinship: Kinship,
+    query_calendar: Query<&Calendar>,
+    query: Query<(Entity, &Name, &Devotion, &Personality, Has<Heretic>), With<Alive>>,
+) {
+    if !is_decade_end(query_calendar.single()) {
+        return;
+    }
+    let mut rng = thread_rng();
+
+    for (person, name, devotion, personality, heretic) in &query {
+        if heretic {
+            if !rng.gen_bool(HERESY_SPREAD_CHANCE) {
+                continue;
+            }
+            let swayable: Vec<Entity> = kinship
+                .close_kin(person)
+                .into_iter()
+                .filter(|kin| {
+                    query.get(*kin).is_ok_and(|(_, _, devotion, _, heretic)| {
+                        !heretic && devotion.fervour < STEADFAST_FERVOUR
+                    })
+                })
+                .collect();
+            let Some((convert, convert_name)) = swayable
+                .choose(&mut rng)
+                .and_then(|kin| Some((*kin, query.get(*kin).ok()?.1)))
+            else {
+                continue;
+            };
+            commands.entity(convert).insert(Heretic);
+            info!(
+                "{} {} leads {} {} into heresy",
+                name.first, name.last, convert_name.first, convert_name.last
+            );
+        } else if devotion.fervour < HERESY_FERVOUR
+            && rng.gen_bool(HERESY_CHANCE * personality.ambition * (1.0 - personality.piety))
+        {
+            commands.entity(person).insert(Heretic);
+            info!("{} {} renounces the High Houses", name.first, name.last);
+        }
+    }
+}
+
+/// Priests denounce to the law the heretics who turned from their house, and those convicted are
+/// made to recant
+fn denounce_heretics(
+    mut commands: Commands,
+    mut ev_verdict: EventReader<VerdictEvent>,
+    mut ev_crime: EventWriter<CrimeEvent>,
+    query_calendar: Query<&Calendar>,
+    query_priests: Query<(Entity, &Priest), (With<Alive>, Without<Heretic>)>,
+    mut query_heretics: Query<(Entity, &mut Devotion), (With<Heretic>, With<Alive>)>,
+) {
+    for event in ev_verdict.read() {
+        if event.crime() != CrimeKind::Heresy || event.sentence().is_none() {
+            continue;
+        }
+        if let Ok((heretic, mut devotion)) = query_heretics.get_mut(event.offender()) {
+            commands.entity(heretic).remove::<Heretic>();
+            devotion.fervour = RECANTED_FERVOUR;
+        }
+    }
+
+    if !is_decade_end(query_calendar.single()) {
+        return;
+    }
+    let mut rng = thread_rng();
+
+    for (heretic, devotion) in &query_heretics {
+        if !rng.gen_bool(DENOUNCE_CHANCE) {
+            continue;
+        }
+        let accuser = query_priests
+            .iter()
+            .find(|(_, priest)| priest.house == devotion.house);
+        if let Some((priest, _)) = accuser {
+            ev_crime.send(CrimeEvent::new(
+                CrimeKind::Heresy,
+                heretic,
+                priest,
+                HERESY_EVIDENCE,
+            ));
+        }
+    }
+}
+
+pub struct ReligionPlugin;
+
+impl Plugin for ReligionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, found_temples).add_systems(
+            Update,
+            (
+                kindle_faith,
+                daily_prayers,
+                hold_rites,
+                appoint_priests,
+                convert_faithful,
+                spread_heresy,
+                denounce_heretics,
+            )
+                .chain()
+                .run_if(in_state(RunState::Running)),
+        );
+    }
+}
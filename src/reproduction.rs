@@ -1,16 +1,75 @@
-use crate::life::{CheatDeathEvent, DeathEvent};
-use crate::moon::Moon;
-use crate::people::{AssignedMoonHouse, Children, Name, Person, PersonBundle, Siblings};
+use crate::age::Age;
+use crate::household::InHousehold;
+use crate::life::{Alive, PerilEvent};
+use crate::marriage::Spouse;
+use crate::moon::{Moon, MoonHouse};
+use crate::naming::Namer;
+use crate::people::{AssignedMoonHouse, Name, Person, PersonBundle, Twins, Zygosity};
+use crate::personality::Personality;
+use crate::sex::{Sex, SexRatios};
 use crate::state::RunState;
+use crate::traits::{describe_inheritance, Traits};
 use bevy::{prelude::*, utils::HashSet};
 use rand::{distributions::Bernoulli, prelude::*};
 use statrs::distribution::{Continuous, Normal};
 
-#[derive(Component, Debug)]
-pub struct ChildBearing;
-
 pub const HUMAN_PREGNANCY_LENGTH: i32 = 266;
 pub const HUMAN_PREGNANCY_STD: i32 = 16;
+/// Each extra baby in the womb brings the birth forward by about three weeks
+const MULTIPLE_TERM_REDUCTION: i32 = 21;
+/// Each extra baby makes a successful birth this much less likely
+const MULTIPLE_BIRTH_RISK: f64 = 0.85;
+
+const FERTILE_AGE_START: i32 = 16;
+const FERTILE_AGE_END: i32 = 45;
+const DAILY_CONCEPTION_CHANCE: f64 = 0.005;
+
+/// How often conceptions end up as more than one baby
+#[derive(Resource, Debug)]
+pub struct MultipleBirthRates {
+    pub identical_twins: f64,
+    pub fraternal_twins: f64,
+    pub triplets: f64,
+    /// How much more likely multiples are when conceived under House Dream
+    pub dream_multiplier: f64,
+}
+
+impl Default for MultipleBirthRates {
+    fn default() -> Self {
+        MultipleBirthRates {
+            identical_twins: 0.004,
+            fraternal_twins: 0.012,
+            triplets: 0.0002,
+            dream_multiplier: 3.0,
+        }
+    }
+}
+
+impl MultipleBirthRates {
+    /// Decide how many babies a conception will produce, and whether they share an egg
+    fn roll(&self, house: &MoonHouse, rng: &mut ThreadRng) -> (i32, Zygosity) {
+        let multiplier = if *house == MoonHouse::Dream {
+            self.dream_multiplier
+        } else {
+            1.0
+        };
+
+        let roll: f64 = rng.gen();
+        let triplets = self.triplets * multiplier;
+        let identical = triplets + self.identical_twins * multiplier;
+        let fraternal = identical + self.fraternal_twins * multiplier;
+
+        if roll < triplets {
+            (3, Zygosity::Fraternal)
+        } else if roll < identical {
+            (2, Zygosity::Identical)
+        } else if roll < fraternal {
+            (2, Zygosity::Fraternal)
+        } else {
+            (1, Zygosity::Fraternal)
+        }
+    }
+}
 
 #[derive(Component, Debug)]
 pub struct Pregnancy {
@@ -19,10 +78,24 @@ pub struct Pregnancy {
     term: i32,
     progress: i32,
     father: Entity,
+    fetuses: i32,
+    zygosity: Zygosity,
 }
 
 impl Pregnancy {
     pub fn new(mean_term: i32, std_term: i32, father: Entity) -> Self {
+        Pregnancy::with_fetuses(mean_term, std_term, father, 1, Zygosity::Fraternal)
+    }
+
+    pub fn with_fetuses(
+        mean_term: i32,
+        std_term: i32,
+        father: Entity,
+        fetuses: i32,
+        zygosity: Zygosity,
+    ) -> Self {
+        let mean_term = mean_term - MULTIPLE_TERM_REDUCTION * (fetuses - 1);
+
         let mut rng = thread_rng();
         let norm_dist = Normal::new(mean_term.into(), std_term.into()).unwrap();
         let term = norm_dist.sample(&mut rng) as i32;
@@ -33,6 +106,8 @@ impl Pregnancy {
             term,
             progress: 0,
             father,
+            fetuses,
+            zygosity,
         }
     }
 }
@@ -44,12 +119,38 @@ struct GiveBirthEvent {
     progress: i32,
     mean_term: i32,
     std_term: i32,
+    fetuses: i32,
+    zygosity: Zygosity,
 }
 
 #[derive(Event, Debug)]
 struct SuccessfulBirthEvent {
     mother: Entity,
     father: Entity,
+    babies: i32,
+    zygosity: Zygosity,
+}
+
+/// Sent once a baby has been born and spawned
+#[derive(Event, Debug)]
+pub struct BirthEvent {
+    child: Entity,
+    mother: Entity,
+    father: Entity,
+}
+
+impl BirthEvent {
+    pub fn child(&self) -> Entity {
+        self.child
+    }
+
+    pub fn mother(&self) -> Entity {
+        self.mother
+    }
+
+    pub fn father(&self) -> Entity {
+        self.father
+    }
 }
 
 #[derive(Event, Debug)]
@@ -59,9 +160,53 @@ struct UnsuccessfulBirthEvent {
     term_diff: i32,
 }
 
+fn handle_conception(
+    mut commands: Commands,
+    rates: Res<MultipleBirthRates>,
+    query_moon: Query<&Moon>,
+    query_mothers: Query<
+        (Entity, &Name, &Age, &Sex, &Spouse),
+        (With<Person>, With<Alive>, Without<Pregnancy>),
+    >,
+    query_fathers: Query<&Sex, With<Alive>>,
+) {
+    let mut rng = thread_rng();
+    let bernoulli_dist = Bernoulli::new(DAILY_CONCEPTION_CHANCE).unwrap();
+    let moon = query_moon.single();
+
+    for (mother, name, age, sex, spouse) in &query_mothers {
+        let fertile =
+            sex.can_bear_children() && (FERTILE_AGE_START..FERTILE_AGE_END).contains(&age.years());
+        let father_fertile = query_fathers
+            .get(spouse.entity)
+            .is_ok_and(|father_sex| !father_sex.can_bear_children());
+        if !fertile || !father_fertile || !bernoulli_dist.sample(&mut rng) {
+            continue;
+        }
+
+        let (fetuses, zygosity) = rates.roll(&moon.house, &mut rng);
+        match fetuses {
+            1 => info!("{} {} is expecting a baby", name.first, name.last),
+            2 => info!(
+                "{} {} is expecting {:?} twins",
+                name.first, name.last, zygosity
+            ),
+            n => info!("{} {} is expecting {} babies", name.first, name.last, n),
+        }
+
+        commands.entity(mother).insert(Pregnancy::with_fetuses(
+            HUMAN_PREGNANCY_LENGTH,
+            HUMAN_PREGNANCY_STD,
+            spouse.entity,
+            fetuses,
+            zygosity,
+        ));
+    }
+}
+
 fn handle_pregnancy(
     mut ev_give_birth: EventWriter<GiveBirthEvent>,
-    mut query: Query<(Entity, &Name, &mut Pregnancy), (With<Person>, With<ChildBearing>)>,
+    mut query: Query<(Entity, &Name, &mut Pregnancy), With<Person>>,
 ) {
     for (mother, name, mut pregnancy) in &mut query {
         pregnancy.progress += 1;
@@ -77,6 +222,8 @@ fn handle_pregnancy(
                 progress: pregnancy.progress,
                 mean_term: pregnancy.mean_term,
                 std_term: pregnancy.std_term,
+                fetuses: pregnancy.fetuses,
+                zygosity: pregnancy.zygosity,
             });
         }
     }
@@ -104,6 +251,8 @@ fn handle_give_birth(
         let pdf_at_mean = problem_dist.pdf(event.mean_term.into());
         debug!("pdf_at_mean: {}", pdf_at_mean);
         let mut raw_p = pdf_at_sample / pdf_at_mean;
+        // carrying more than one baby is riskier
+        raw_p *= MULTIPLE_BIRTH_RISK.powi(event.fetuses - 1);
 
         let mother_house = query_assigned_house
             .get(event.mother)
@@ -130,6 +279,8 @@ fn handle_give_birth(
             ev_successful_birth.send(SuccessfulBirthEvent {
                 mother: event.mother,
                 father: event.father,
+                babies: event.fetuses,
+                zygosity: event.zygosity,
             });
         } else {
             ev_unsuccessful_birth.send(UnsuccessfulBirthEvent {
@@ -144,98 +295,150 @@ fn handle_give_birth(
 fn handle_successful_birth(
     mut commands: Commands,
     mut ev_successful_birth: EventReader<SuccessfulBirthEvent>,
-    mut query_parents: Query<(&mut Children, &Name)>,
-    mut query_siblings: Query<&mut Siblings>,
+    mut ev_birth: EventWriter<BirthEvent>,
+    namer: Namer,
+    sex_ratios: Res<SexRatios>,
+    query_parents: Query<(
+        &Name,
+        Option<&InHousehold>,
+        Option<&Traits>,
+        Option<&Personality>,
+    )>,
     query_moon: Query<&Moon>,
 ) {
+    let mut rng = thread_rng();
+
     for event in ev_successful_birth.read() {
         // create a set of parents for the new child
         let new_child_parents = HashSet::from([event.mother, event.father]);
 
-        // create a set of siblings for the new child
-        let mut new_child_siblings = HashSet::new();
-        // get the children of the mother
-        if let Ok((children_mother, _)) = query_parents.get(event.mother) {
-            for child in &children_mother.set {
-                new_child_siblings.insert(*child);
-            }
-        }
-        // get the children of the father
-        if let Ok((children_father, _)) = query_parents.get(event.father) {
-            for child in &children_father.set {
-                new_child_siblings.insert(*child);
+        let moon = query_moon.single();
+
+        let parents = (
+            query_parents.get(event.mother),
+            query_parents.get(event.father),
+        );
+        let parent_traits = match parents {
+            (Ok((mother_name, _, Some(mother), _)), Ok((father_name, _, Some(father), _))) => {
+                Some([(mother_name, mother), (father_name, father)])
             }
-        }
+            _ => None,
+        };
+        let inherit_traits = |rng: &mut ThreadRng| match parent_traits {
+            Some([(_, mother), (_, father)]) => Traits::inherit(mother, father, rng),
+            None => Traits::random(rng),
+        };
 
-        let moon = query_moon.single();
+        let parent_personalities = match parents {
+            (Ok((_, _, _, Some(mother))), Ok((_, _, _, Some(father)))) => Some((mother, father)),
+            _ => None,
+        };
+        let inherit_personality = |rng: &mut ThreadRng| match parent_personalities {
+            Some((mother, father)) => Personality::inherit(mother, father, &moon.house, rng),
+            None => Personality::random(rng),
+        };
+
+        // identical twins share their sex, their traits and their temperament
+        let shared_sex = sex_ratios.sex(&mut rng);
+        let shared_traits = inherit_traits(&mut rng);
+        let shared_personality = inherit_personality(&mut rng);
+        let mut born_together = Vec::new();
+        let mut taken_names = HashSet::new();
+
+        for _ in 0..event.babies {
+            let (sex, traits, personality) = match event.zygosity {
+                Zygosity::Identical => (
+                    shared_sex,
+                    shared_traits.clone(),
+                    shared_personality.clone(),
+                ),
+                Zygosity::Fraternal => (
+                    sex_ratios.sex(&mut rng),
+                    inherit_traits(&mut rng),
+                    inherit_personality(&mut rng),
+                ),
+            };
+            let gender = sex_ratios.gender(sex, &mut rng);
+            let (name, culture) =
+                namer.name_newborn(event.mother, event.father, gender, &taken_names, &mut rng);
+            taken_names.insert(name.first.clone());
 
-        // TODO: generalise this
-        let new_child = commands
-            .spawn(PersonBundle::new_child(
-                "Penny",
-                "Morales-Allan",
-                new_child_parents,
-                // gotta clone cos we're gonna use it again later
-                new_child_siblings.clone(),
+            let mut new_child_bundle = PersonBundle::new_child(
+                &name.first,
+                &name.last,
+                sex,
+                new_child_parents.clone(),
                 moon.house.clone(),
                 0,
-            ))
-            .id();
+            );
+            new_child_bundle.gender = gender;
+            new_child_bundle.culture = culture;
+            if let Some(parents) = parent_traits {
+                describe_inheritance(&name, &traits, parents);
+            }
+            new_child_bundle.traits = traits;
+            debug!(
+                "{} {} is driven by {:?}",
+                name.first,
+                name.last,
+                personality.dominant()
+            );
+            new_child_bundle.personality = personality;
+            let new_child = commands.spawn(new_child_bundle).id();
 
-        // add the kid to the hashset of children for each parent
-        if let Ok((mut children_mother, name_mother)) = query_parents.get_mut(event.mother) {
-            info!("{} {} gave birth!", name_mother.first, name_mother.last);
-            children_mother.set.insert(new_child);
-        }
-        if let Ok((mut children_father, _name_father)) = query_parents.get_mut(event.father) {
-            children_father.set.insert(new_child);
+            if let Ok((name_mother, home, _, _)) = query_parents.get(event.mother) {
+                info!(
+                    "{} {} gave birth to {} {}!",
+                    name_mother.first, name_mother.last, name.first, name.last
+                );
+
+                // the baby lives with its mother
+                if let Some(home) = home {
+                    commands.entity(new_child).insert(InHousehold {
+                        household: home.household,
+                    });
+                }
+            }
+
+            born_together.push(new_child);
         }
-        // insert the new child into the set of siblings for each of their siblings
-        let mut siblings_iter = query_siblings.iter_many_mut(&new_child_siblings);
-        while let Some(mut siblings_of_sibling) = siblings_iter.fetch_next() {
-            siblings_of_sibling.set.insert(new_child);
+
+        for &new_child in &born_together {
+            // babies born together are twins
+            let zygosity = born_together
+                .iter()
+                .filter(|twin| **twin != new_child)
+                .map(|twin| (*twin, event.zygosity))
+                .collect();
+            commands.entity(new_child).insert(Twins { zygosity });
+
+            ev_birth.send(BirthEvent {
+                child: new_child,
+                mother: event.mother,
+                father: event.father,
+            });
         }
     }
 }
 
 fn handle_unsuccessful_birth(
     mut ev_unsuccessful_birth: EventReader<UnsuccessfulBirthEvent>,
-    mut ev_death: EventWriter<DeathEvent>,
-    mut ev_cheated_death: EventWriter<CheatDeathEvent>,
-    query_moon: Query<&Moon>,
-    query_mother: Query<(&Name, &AssignedMoonHouse)>,
+    mut ev_peril: EventWriter<PerilEvent>,
+    query_mother: Query<&Name>,
 ) {
     // TODO: make some fucked up shit happen
     // mum dies? baby dies? :(
-    let current_moon_house = &query_moon.single().house;
-
     for event in ev_unsuccessful_birth.read() {
-        let (name, mother_assigned_house) = query_mother.get(event.mother).unwrap();
+        let name = query_mother.get(event.mother).unwrap();
         debug!(
             "Handling unsuccessful birth for {} {}",
             name.first, name.last
         );
 
-        // is the mother currently in her favoured house?
-        let in_favoured = *current_moon_house == mother_assigned_house.house;
-
         debug!("Term Diff: {}", event.term_diff);
         if event.term_diff >= 0 {
-            if in_favoured {
-                let mut rng = thread_rng();
-                let bernoulli_dist = Bernoulli::new(0.1).unwrap();
-                let cheated_death = bernoulli_dist.sample(&mut rng);
-                debug!("Outcome of cheat death bernoulli trial {}", cheated_death);
-                if cheated_death {
-                    ev_cheated_death.send(CheatDeathEvent::new(
-                        event.mother,
-                        mother_assigned_house.house.clone(),
-                    ));
-                    return;
-                }
-            }
-
-            ev_death.send(DeathEvent::new(event.mother, "Childbirth"));
+            // her house may yet spare her
+            ev_peril.send(PerilEvent::new(event.mother, "Childbirth"));
             // TODO: make the dad get sad?
             // TODO: but also maybe you scorn your house for letting your baby die?
             // TODO: or maybe you're grateful your house intervened to let you live
@@ -253,14 +456,16 @@ impl Plugin for ReproductionPlugin {
         app.add_systems(
             Update,
             (
-                (handle_pregnancy, handle_give_birth).chain(),
+                (handle_conception, handle_pregnancy, handle_give_birth).chain(),
                 (handle_successful_birth, handle_unsuccessful_birth),
             )
                 .chain()
                 .run_if(in_state(RunState::Running)),
         )
+        .init_resource::<MultipleBirthRates>()
         .add_event::<GiveBirthEvent>()
         .add_event::<SuccessfulBirthEvent>()
+        .add_event::<BirthEvent>()
         .add_event::<UnsuccessfulBirthEvent>();
     }
 }
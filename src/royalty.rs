@@ -1,19 +1,67 @@
 use bevy::prelude::*;
+use rand::prelude::*;
 
 use crate::{
     age::Age,
+    calendar::Calendar,
+    dynasty::{Dynasty, InDynasty},
     life::Alive,
-    people::{Name, Person},
+    moon::{ExaltEvent, Moon, MoonHouse},
+    people::{AssignedMoonHouse, Name},
+    personality::{Facet, Personality},
+    regency::{acting_ruler, Regent},
+    reproduction::BirthEvent,
+    sex::Gender,
+    state::RunState,
+    titles::{LandedTitle, TitleRank},
 };
 
-#[derive(Debug)]
+const DAILY_DECREE_CHANCE: f64 = 1.0 / 30.0;
+const LEGITIMACY_LIMIT: i32 = 100;
+/// Where a monarch's legitimacy starts before anything has been done to prove it
+const BASE_LEGITIMACY: i32 = 50;
+/// A new monarch isn't fully accepted until they're crowned
+pub const UNCROWNED_LEGITIMACY: i32 = 30;
+pub const PRESTIGE_LIMIT: i32 = 1000;
+/// Share of the realm's harvests taken in tax, in percent
+const BASE_TAX_RATE: i32 = 10;
+pub const MIN_TAX_RATE: i32 = 5;
+const MAX_TAX_RATE: i32 = 50;
+const TAX_STEP: i32 = 5;
+/// Chance an ambitious monarch's decree is a new tax
+const TAX_DECREE_CHANCE: f64 = 0.3;
+/// Prestige a person gains when the moon enters their High House
+const EXALTED_PRESTIGE: i32 = 5;
+/// Legitimacy a monarch gains when the moon enters their dynasty's High House
+const FAVOURED_LEGITIMACY: i32 = 3;
+
+/// Rank of a royal, how they're styled depends on their gender
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Title {
-    King,
-    Queen,
+    Monarch,
+    Consort,
     Prince,
-    Princess,
-    Duke,
-    Duchess,
+    /// The widow of a late monarch
+    Dowager,
+}
+
+impl Title {
+    pub fn styled(&self, gender: Gender) -> &'static str {
+        use Gender::{Man, NonBinary, Woman};
+        match (self, gender) {
+            (Title::Monarch, Man) => "King",
+            (Title::Monarch | Title::Consort, Woman) => "Queen",
+            (Title::Monarch, NonBinary) => "Monarch",
+            (Title::Consort, Man) => "Prince Consort",
+            (Title::Consort, NonBinary) => "Consort",
+            (Title::Prince, Man) => "Prince",
+            (Title::Prince, Woman) => "Princess",
+            (Title::Prince, NonBinary) => "Royal Highness",
+            (Title::Dowager, Man) => "Prince Dowager",
+            (Title::Dowager, Woman) => "Queen Dowager",
+            (Title::Dowager, NonBinary) => "Dowager",
+        }
+    }
 }
 
 #[derive(Component, Debug)]
@@ -21,20 +69,327 @@ pub struct Royalty {
     pub title: Title,
 }
 
-fn detail_royalty(query: Query<(&Name, &Age, &Royalty), (With<Person>, With<Alive>)>) {
-    debug!("~~ Current Royalty ~~");
-    for (name, age, royalty) in &query {
+/// How rightful a monarch's rule is seen to be, from 0 to 100
+#[derive(Component, Debug)]
+pub struct Legitimacy {
+    pub score: i32,
+}
+
+impl Default for Legitimacy {
+    fn default() -> Self {
+        Legitimacy {
+            score: BASE_LEGITIMACY,
+        }
+    }
+}
+
+impl Legitimacy {
+    pub fn adjust(&mut self, change: i32) {
+        self.score = (self.score + change).clamp(0, LEGITIMACY_LIMIT);
+    }
+}
+
+/// Renown earned from titles, favour and deeds, from 0 to 1000. Claimants and factions draw
+/// their strength from it.
+#[derive(Component, Debug, Default)]
+pub struct Prestige {
+    pub score: i32,
+}
+
+impl Prestige {
+    pub fn adjust(&mut self, change: i32) {
+        self.score = (self.score + change).clamp(0, PRESTIGE_LIMIT);
+    }
+}
+
+/// What the crown takes from the realm, in percent of the harvest
+#[derive(Resource, Debug)]
+pub struct Taxes {
+    pub rate: i32,
+}
+
+impl Default for Taxes {
+    fn default() -> Self {
+        Taxes {
+            rate: BASE_TAX_RATE,
+        }
+    }
+}
+
+impl Taxes {
+    /// How far taxes are above what the realm is used to
+    pub fn burden(&self) -> i32 {
+        self.rate - BASE_TAX_RATE
+    }
+
+    pub fn raise(&mut self) {
+        self.rate = (self.rate + TAX_STEP).min(MAX_TAX_RATE);
+    }
+
+    pub fn lower(&mut self) {
+        self.rate = (self.rate - TAX_STEP).max(MIN_TAX_RATE);
+    }
+}
+
+/// Prestige a title brings its holder each month
+fn monthly_prestige(rank: TitleRank) -> i32 {
+    match rank {
+        TitleRank::Kingdom => 5,
+        TitleRank::Duchy => 3,
+        TitleRank::County => 1,
+    }
+}
+
+/// How someone is presented at court, e.g. "Jack Allan of House Allan (32 Years, 10 Days)"
+fn courtier(name: &Name, age: &Age, dynasty: Option<&Dynasty>) -> String {
+    match dynasty {
+        Some(dynasty) => format!("{} {} of {} ({})", name.first, name.last, dynasty, age),
+        None => format!("{} {} ({})", name.first, name.last, age),
+    }
+}
+
+/// Titles held from `liege`, each followed by the titles held from it in turn
+fn list_vassals<'a>(
+    liege: Option<Entity>,
+    depth: usize,
+    titles: &[(Entity, &'a LandedTitle)],
+    listing: &mut Vec<(usize, &'a LandedTitle)>,
+) {
+    for (entity, title) in titles.iter().filter(|(_, title)| title.liege == liege) {
+        listing.push((depth, title));
+        list_vassals(Some(*entity), depth + 1, titles, listing);
+    }
+}
+
+/// List the royal family, the regent and the holders of every title, at the start of each month
+fn hold_court(
+    query_calendar: Query<&Calendar>,
+    query_people: Query<(&Name, &Age, &Gender, Option<&InDynasty>), With<Alive>>,
+    query_royals: Query<(Entity, &Royalty, Option<&Legitimacy>, &Prestige), With<Alive>>,
+    query_regents: Query<Entity, (With<Regent>, With<Alive>)>,
+    query_titles: Query<(Entity, &LandedTitle)>,
+    query_dynasties: Query<&Dynasty>,
+    query_names: Query<&Name>,
+) {
+    let calendar = query_calendar.single();
+    if calendar.date().month_day != 1 {
+        return;
+    }
+    let present = |person: Entity| {
+        query_people
+            .get(person)
+            .ok()
+            .map(|(name, age, gender, member)| {
+                let dynasty = member.and_then(|member| query_dynasties.get(member.dynasty).ok());
+                (*gender, courtier(name, age, dynasty))
+            })
+    };
+
+    info!("~~ The Royal Court, {} ~~", calendar);
+    let mut royals: Vec<_> = query_royals.iter().collect();
+    royals.sort_by_key(|(_, royalty, _, _)| royalty.title as u8);
+    for (royal, royalty, legitimacy, prestige) in royals {
+        let Some((gender, courtier)) = present(royal) else {
+            continue;
+        };
+        let legitimacy = legitimacy.map_or_else(String::new, |legitimacy| {
+            format!(", legitimacy {}", legitimacy.score)
+        });
         info!(
-            "{:?}: {} {} ({})",
-            royalty.title, name.first, name.last, age
+            "{}: {}{}, prestige {}",
+            royalty.title.styled(gender),
+            courtier,
+            legitimacy,
+            prestige.score
         );
     }
+    let royal_house = query_royals
+        .iter()
+        .find(|(_, royalty, _, _)| royalty.title == Title::Monarch)
+        .and_then(|(monarch, ..)| query_people.get(monarch).ok())
+        .and_then(|(_, _, _, member)| member)
+        .and_then(|member| query_dynasties.get(member.dynasty).ok());
+    if let Some(dynasty) = royal_house {
+        if let Ok(founder) = query_names.get(dynasty.founder) {
+            info!(
+                "The royal {}, founded by {} {}, bears {}",
+                dynasty, founder.first, founder.last, dynasty.arms
+            );
+        }
+    }
+    for regent in &query_regents {
+        if let Some((_, courtier)) = present(regent) {
+            info!("Regent: {}", courtier);
+        }
+    }
+
+    let mut titles: Vec<(Entity, &LandedTitle)> = query_titles.iter().collect();
+    titles.sort_by(|(_, a), (_, b)| a.name.cmp(&b.name));
+    let mut listing = Vec::new();
+    list_vassals(None, 0, &titles, &mut listing);
+    for (depth, title) in listing {
+        let holder = title.holder.and_then(present).map_or_else(
+            || "vacant".to_string(),
+            |(gender, courtier)| format!("{} {}", title.rank.styled(gender), courtier),
+        );
+        let since = title
+            .holder
+            .and(title.history.last())
+            .map_or_else(String::new, |tenure| format!(", since {}", tenure.from));
+        info!("{}{}: {}{}", "  ".repeat(depth), title, holder, since);
+    }
+}
+
+/// Title holders grow in renown for each month they hold their lands
+fn accrue_prestige(
+    query_calendar: Query<&Calendar>,
+    query_titles: Query<&LandedTitle>,
+    mut query_prestige: Query<&mut Prestige, With<Alive>>,
+) {
+    if query_calendar.single().date().month_day != 1 {
+        return;
+    }
+    for title in &query_titles {
+        if let Some(mut prestige) = title
+            .holder
+            .and_then(|holder| query_prestige.get_mut(holder).ok())
+        {
+            prestige.adjust(monthly_prestige(title.rank));
+        }
+    }
+}
+
+/// The High Houses lend renown to those they exalt, and the moon entering the royal dynasty's
+/// house is taken as a sign the monarch rules by right
+fn house_favour(
+    mut ev_exalt: EventReader<ExaltEvent>,
+    mut last_house: Local<Option<MoonHouse>>,
+    query_moon: Query<&Moon>,
+    query_dynasties: Query<&Dynasty>,
+    mut query_monarchs: Query<(&Name, &Royalty, &InDynasty, &mut Legitimacy), With<Alive>>,
+    mut query_prestige: Query<&mut Prestige, With<Alive>>,
+) {
+    for event in ev_exalt.read() {
+        if let Ok(mut prestige) = query_prestige.get_mut(event.exalted()) {
+            prestige.adjust(EXALTED_PRESTIGE);
+        }
+    }
+
+    let house = &query_moon.single().house;
+    if last_house.as_ref() == Some(house) {
+        return;
+    }
+    let entered = last_house.is_some();
+    *last_house = Some(house.clone());
+    if !entered {
+        return;
+    }
+
+    for (name, royalty, member, mut legitimacy) in &mut query_monarchs {
+        let favoured = query_dynasties
+            .get(member.dynasty)
+            .is_ok_and(|dynasty| dynasty.house == *house);
+        if royalty.title == Title::Monarch && favoured {
+            legitimacy.adjust(FAVOURED_LEGITIMACY);
+            info!(
+                "The moon enters High House {}, favouring {} {} and their line",
+                house, name.first, name.last
+            );
+        }
+    }
+}
+
+/// Children born to the monarch and their consort are princes and princesses from birth
+fn title_royal_births(
+    mut commands: Commands,
+    mut ev_birth: EventReader<BirthEvent>,
+    query_royals: Query<&Royalty>,
+) {
+    for event in ev_birth.read() {
+        let royal_parent = [event.mother(), event.father()].into_iter().any(|parent| {
+            query_royals
+                .get(parent)
+                .is_ok_and(|royalty| matches!(royalty.title, Title::Monarch | Title::Consort))
+        });
+        if royal_parent {
+            commands.entity(event.child()).insert(Royalty {
+                title: Title::Prince,
+            });
+        }
+    }
+}
+
+/// Every so often the crown does something, what depends on who rules: the monarch, or their
+/// regent while they're too young
+fn royal_decree(
+    mut taxes: ResMut<Taxes>,
+    query_monarchs: Query<(Entity, &Name, &Gender, &Royalty), With<Alive>>,
+    query_regents: Query<(Entity, &Regent), With<Alive>>,
+    query_rulers: Query<(&Name, &Personality, &AssignedMoonHouse), With<Alive>>,
+) {
+    let mut rng = thread_rng();
+
+    for (monarch, name, gender, royalty) in &query_monarchs {
+        if !matches!(royalty.title, Title::Monarch) || !rng.gen_bool(DAILY_DECREE_CHANCE) {
+            continue;
+        }
+        let ruler = acting_ruler(monarch, &query_regents);
+        let Ok((ruler_name, personality, assigned_house)) = query_rulers.get(ruler) else {
+            continue;
+        };
+
+        let decree = match personality.dominant() {
+            Facet::Ambition if rng.gen_bool(TAX_DECREE_CHANCE) && taxes.rate < MAX_TAX_RATE => {
+                taxes.raise();
+                format!("raises taxes to {}% to pay for a war", taxes.rate)
+            }
+            Facet::Ambition => "raises the levies to prepare for war".into(),
+            Facet::Piety => format!(
+                "orders a temple built to High House {}",
+                assigned_house.house
+            ),
+            Facet::Cruelty => "has a dissenter hanged in the square".into(),
+            Facet::Loyalty => "rewards the loyal retainers of the court".into(),
+            Facet::Lust => "throws a lavish feast".into(),
+        };
+
+        if ruler == monarch {
+            info!(
+                "{} {} {} {}",
+                royalty.title.styled(*gender),
+                name.first,
+                name.last,
+                decree
+            );
+        } else {
+            info!(
+                "Regent {} {}, ruling for {} {} {}, {}",
+                ruler_name.first,
+                ruler_name.last,
+                royalty.title.styled(*gender),
+                name.first,
+                name.last,
+                decree
+            );
+        }
+    }
 }
 
 pub struct RoyaltyPlugin;
 
 impl Plugin for RoyaltyPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, detail_royalty);
+        app.init_resource::<Taxes>()
+            .add_systems(Update, hold_court.run_if(in_state(RunState::Running)));
+        app.add_systems(
+            Update,
+            (
+                royal_decree,
+                title_royal_births,
+                accrue_prestige,
+                house_favour,
+            )
+                .run_if(in_state(RunState::Running)),
+        );
     }
 }
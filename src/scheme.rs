@@ -0,0 +1,444 @@
+use std::fmt;
+
+use bevy::prelude::*;
+use rand::prelude::*;
+
+use crate::{
+    calendar::Calendar,
+    crime::{Exiled, Imprisoned},
+    life::{Alive, DeathEvent},
+    memories::{Memories, MemoryKind},
+    people::Name,
+    personality::Personality,
+    relationships::{OpinionEvent, RelationKind, Relationships},
+    royalty::{Prestige, Royalty, Title},
+    state::RunState,
+    succession::{Claimants, SuccessionRules},
+    traits::{Attribute, Traits},
+};
+
+/// Chance each day that someone with a motive starts scheming
+const DAILY_SCHEME_CHANCE: f64 = 0.001;
+/// People think this little of someone they hold a grudge against
+const GRUDGE_OPINION: i32 = -40;
+/// Painful memories about someone this strong leave a grudge
+const GRUDGE_WEIGHT: f64 = -0.4;
+/// People think this well of someone they'd try to seduce, anyone will try a royal they don't
+/// dislike
+const DESIRE_OPINION: i32 = 20;
+/// Progress a scheme makes each day with a plotter of average wit, schemes are done at 1
+const DAILY_PROGRESS: f64 = 0.02;
+/// Chance each day a scheme is found out, for a plotter of average wit
+const DAILY_DISCOVERY_CHANCE: f64 = 0.005;
+/// Royals are watched more closely than anyone else
+const ROYAL_VIGILANCE: f64 = 2.0;
+const ASSASSINATION_SUCCESS_CHANCE: f64 = 0.6;
+const BLACKMAIL_SUCCESS_CHANCE: f64 = 0.7;
+/// Chance a scheme that fails at the last is found out
+const FAILED_DISCOVERY_CHANCE: f64 = 0.5;
+const BLACKMAIL_PRESTIGE: i32 = 30;
+const EXPOSED_PRESTIGE: i32 = -40;
+const BLACKMAILED_OPINION: i32 = -30;
+const PLOTTED_OPINION: i32 = -60;
+const AFFAIR_WEIGHT: f64 = 0.5;
+const PLOTTED_WEIGHT: f64 = -0.6;
+
+/// What a plotter is after
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SchemeKind {
+    Assassination,
+    Seduction,
+    Blackmail,
+}
+
+impl SchemeKind {
+    /// What the scheme would do to its target, e.g. "murder them"
+    pub fn aim(self) -> &'static str {
+        match self {
+            SchemeKind::Assassination => "murder them",
+            SchemeKind::Seduction => "seduce them",
+            SchemeKind::Blackmail => "blackmail them",
+        }
+    }
+}
+
+impl fmt::Display for SchemeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self {
+            SchemeKind::Assassination => "assassination",
+            SchemeKind::Seduction => "seduction",
+            SchemeKind::Blackmail => "blackmail",
+        };
+        write!(f, "{kind}")
+    }
+}
+
+/// A secret plot against someone, worked on a little each day
+#[derive(Component, Debug)]
+pub struct Scheme {
+    pub kind: SchemeKind,
+    pub plotter: Entity,
+    pub target: Entity,
+    /// How close the scheme is to done, from 0 to 1
+    pub progress: f64,
+    pub days: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SchemeOutcome {
+    Succeeded,
+    Failed,
+    /// Found out before or as it came to a head
+    Exposed,
+}
+
+/// A scheme has come to an end, one way or another
+#[derive(Event, Debug)]
+pub struct SchemeEvent {
+    kind: SchemeKind,
+    plotter: Entity,
+    target: Entity,
+    outcome: SchemeOutcome,
+}
+
+impl SchemeEvent {
+    pub fn new(kind: SchemeKind, plotter: Entity, target: Entity, outcome: SchemeOutcome) -> Self {
+        SchemeEvent {
+            kind,
+            plotter,
+            target,
+            outcome,
+        }
+    }
+
+    pub fn kind(&self) -> SchemeKind {
+        self.kind
+    }
+
+    pub fn plotter(&self) -> Entity {
+        self.plotter
+    }
+
+    pub fn target(&self) -> Entity {
+        self.target
+    }
+
+    pub fn outcome(&self) -> SchemeOutcome {
+        self.outcome
+    }
+}
+
+/// Who the plotter holds a grudge against: an enemy, a rival, or someone behind a painful
+/// memory, whoever they think least of
+fn grudge(
+    plotter: Entity,
+    memories: &Memories,
+    relationships: &Relationships,
+    claimants: &Claimants,
+) -> Option<Entity> {
+    let remembered = memories
+        .events
+        .iter()
+        .filter(|memory| memory.weight <= GRUDGE_WEIGHT)
+        .filter_map(|memory| memory.about);
+    let disliked = relationships
+        .known_by(plotter)
+        .filter(|(_, relationship)| relationship.opinion <= GRUDGE_OPINION)
+        .map(|(other, _)| other)
+        .chain(relationships.with_kind(plotter, RelationKind::Enemy))
+        .chain(relationships.with_kind(plotter, RelationKind::Rival));
+    remembered
+        .chain(disliked)
+        .filter(|other| *other != plotter && claimants.is_alive(*other))
+        .min_by_key(|other| relationships.opinion(plotter, *other))
+}
+
+/// Each day a few people with a motive start a scheme: the cruel murder those they hold a
+/// grudge against or who stand between them and the throne, the lustful seduce those they
+/// desire, and the ambitious blackmail the most renowned of those with a secret to keep
+fn hatch_schemes(
+    mut commands: Commands,
+    relationships: Res<Relationships>,
+    rules: Res<SuccessionRules>,
+    claimants: Claimants,
+    query_people: Query<
+        (Entity, &Name, &Personality, &Memories, &Prestige),
+        (With<Alive>, Without<Imprisoned>, Without<Exiled>),
+    >,
+    query_royals: Query<(Entity, &Royalty), With<Alive>>,
+    query_schemes: Query<&Scheme>,
+) {
+    let mut rng = thread_rng();
+    let monarch = query_royals
+        .iter()
+        .find(|(_, royalty)| royalty.title == Title::Monarch)
+        .map(|(monarch, _)| monarch);
+
+    // an affair or a murder in the making is worth keeping quiet
+    let secrets: Vec<(Entity, i32)> = query_people
+        .iter()
+        .filter(|(person, _, _, memories, _)| {
+            memories
+                .events
+                .iter()
+                .any(|memory| memory.kind == MemoryKind::Affair)
+                || query_schemes.iter().any(|scheme| {
+                    scheme.plotter == *person && scheme.kind == SchemeKind::Assassination
+                })
+        })
+        .map(|(person, .., prestige)| (person, prestige.score))
+        .collect();
+
+    for (plotter, name, personality, memories, _) in &query_people {
+        if !rng.gen_bool(DAILY_SCHEME_CHANCE)
+            || !claimants.is_adult(plotter)
+            || query_schemes.iter().any(|scheme| scheme.plotter == plotter)
+        {
+            continue;
+        }
+
+        // whoever is ahead of them in the line of succession
+        let in_the_way = monarch.and_then(|monarch| {
+            let line = claimants.ranked(monarch, rules.law);
+            let place = line.iter().position(|person| *person == plotter)?;
+            Some(line[..place].last().copied().unwrap_or(monarch))
+        });
+        let grudge = grudge(plotter, memories, &relationships, &claimants);
+        let kin = claimants.kinship().close_kin(plotter);
+        let desired = query_royals
+            .iter()
+            .map(|(royal, _)| royal)
+            .chain(relationships.known_by(plotter).map(|(other, _)| other))
+            .filter(|other| {
+                *other != plotter
+                    && claimants.is_alive(*other)
+                    && claimants.is_adult(*other)
+                    && !kin.contains(other)
+                    && relationships.kind(plotter, *other) != Some(RelationKind::Lover)
+                    && relationships.opinion(plotter, *other)
+                        >= if query_royals.contains(*other) {
+                            0
+                        } else {
+                            DESIRE_OPINION
+                        }
+            })
+            .max_by_key(|other| relationships.opinion(plotter, *other));
+        let secret_keeper = secrets
+            .iter()
+            .filter(|(person, _)| *person != plotter)
+            .max_by_key(|(_, prestige)| *prestige)
+            .map(|(person, _)| *person);
+
+        let options = [
+            (
+                SchemeKind::Assassination,
+                grudge.or(in_the_way),
+                personality.cruelty * (1.0 - personality.loyalty),
+            ),
+            (
+                SchemeKind::Seduction,
+                desired,
+                personality.lust * (1.0 - personality.loyalty),
+            ),
+            (
+                SchemeKind::Blackmail,
+                secret_keeper,
+                personality.ambition * (1.0 - personality.piety),
+            ),
+        ];
+        let Ok((kind, Some(target), _)) = options
+            .iter()
+            .filter(|(_, target, _)| target.is_some())
+            .collect::<Vec<_>>()
+            .choose_weighted(&mut rng, |(_, _, weight)| *weight)
+            .copied()
+            .copied()
+        else {
+            continue;
+        };
+
+        debug!("{} {} begins a {} scheme", name.first, name.last, kind);
+        commands.spawn(Scheme {
+            kind,
+            plotter,
+            target,
+            progress: 0.0,
+            days: 0,
+        });
+    }
+}
+
+/// Schemes creep forward each day, faster for a witty plotter, and may be found out on the way.
+/// Once ready they come to a head.
+fn advance_schemes(
+    mut commands: Commands,
+    relationships: Res<Relationships>,
+    mut query_schemes: Query<(Entity, &mut Scheme)>,
+    query_people: Query<(&Personality, &Traits, Option<&Royalty>), With<Alive>>,
+    mut ev_scheme: EventWriter<SchemeEvent>,
+) {
+    let mut rng = thread_rng();
+
+    for (entity, mut scheme) in &mut query_schemes {
+        let (Ok((_, traits, _)), Ok((target_personality, _, target_royalty))) = (
+            query_people.get(scheme.plotter),
+            query_people.get(scheme.target),
+        ) else {
+            // the plotter or their target is dead, so there's nothing left to plot
+            commands.entity(entity).despawn();
+            continue;
+        };
+
+        let wit = traits.attribute(Attribute::Wit);
+        let vigilance = if target_royalty.is_some() {
+            ROYAL_VIGILANCE
+        } else {
+            1.0
+        };
+        scheme.days += 1;
+        scheme.progress += DAILY_PROGRESS * (0.5 + wit);
+
+        let outcome = if rng.gen_bool((DAILY_DISCOVERY_CHANCE * (1.5 - wit) * vigilance).min(1.0)) {
+            SchemeOutcome::Exposed
+        } else if scheme.progress < 1.0 {
+            continue;
+        } else {
+            let success_chance = match scheme.kind {
+                SchemeKind::Assassination => ASSASSINATION_SUCCESS_CHANCE,
+                // an advance lands if the target is keen on them, or just keen
+                SchemeKind::Seduction => (target_personality.lust
+                    + f64::from(relationships.opinion(scheme.target, scheme.plotter)) / 100.0)
+                    .clamp(0.05, 0.95),
+                SchemeKind::Blackmail => BLACKMAIL_SUCCESS_CHANCE,
+            };
+            if rng.gen_bool(success_chance) {
+                SchemeOutcome::Succeeded
+            } else if rng.gen_bool(FAILED_DISCOVERY_CHANCE) {
+                SchemeOutcome::Exposed
+            } else {
+                SchemeOutcome::Failed
+            }
+        };
+
+        commands.entity(entity).despawn();
+        ev_scheme.send(SchemeEvent::new(
+            scheme.kind,
+            scheme.plotter,
+            scheme.target,
+            outcome,
+        ));
+    }
+}
+
+/// Poison kills, seduction starts affairs, blackmail moves prestige from the target to the
+/// plotter. An exposed plotter is disgraced, and the courts deal with the rest.
+fn resolve_schemes(
+    mut relationships: ResMut<Relationships>,
+    mut ev_scheme: EventReader<SchemeEvent>,
+    mut ev_death: EventWriter<DeathEvent>,
+    mut ev_opinion: EventWriter<OpinionEvent>,
+    query_calendar: Query<&Calendar>,
+    mut query_people: Query<(&mut Memories, &mut Prestige), With<Alive>>,
+) {
+    let date = query_calendar.single().date();
+
+    for event in ev_scheme.read() {
+        let (plotter, target) = (event.plotter(), event.target());
+        match (event.kind(), event.outcome()) {
+            (_, SchemeOutcome::Failed) => {}
+            (SchemeKind::Assassination, SchemeOutcome::Succeeded) => {
+                ev_death.send(DeathEvent::new(target, "Poisoned"));
+            }
+            (SchemeKind::Seduction, SchemeOutcome::Succeeded) => {
+                relationships.make_lovers(plotter, target);
+                for (person, lover) in [(plotter, target), (target, plotter)] {
+                    if let Ok((mut memories, ..)) = query_people.get_mut(person) {
+                        memories.remember(MemoryKind::Affair, Some(lover), date, AFFAIR_WEIGHT);
+                    }
+                }
+            }
+            (SchemeKind::Blackmail, SchemeOutcome::Succeeded) => {
+                if let Ok((_, mut prestige, ..)) = query_people.get_mut(target) {
+                    prestige.adjust(-BLACKMAIL_PRESTIGE);
+                }
+                if let Ok((_, mut prestige, ..)) = query_people.get_mut(plotter) {
+                    prestige.adjust(BLACKMAIL_PRESTIGE);
+                }
+                ev_opinion.send(OpinionEvent::new(
+                    target,
+                    plotter,
+                    BLACKMAILED_OPINION,
+                    "blackmailed them",
+                ));
+            }
+            (kind, SchemeOutcome::Exposed) => {
+                ev_opinion.send(OpinionEvent::new(
+                    target,
+                    plotter,
+                    PLOTTED_OPINION,
+                    "plotted against them",
+                ));
+                if let Ok((mut memories, ..)) = query_people.get_mut(target) {
+                    memories.remember(
+                        MemoryKind::Plotted { scheme: kind },
+                        Some(plotter),
+                        date,
+                        PLOTTED_WEIGHT,
+                    );
+                }
+                if let Ok((_, mut prestige)) = query_people.get_mut(plotter) {
+                    prestige.adjust(EXPOSED_PRESTIGE);
+                }
+            }
+        }
+    }
+}
+
+/// Spread word of how schemes ended, those that were found out at least
+fn tell_scheme_stories(mut ev_scheme: EventReader<SchemeEvent>, query: Query<&Name>) {
+    for event in ev_scheme.read() {
+        let Ok([plotter, target]) = query.get_many([event.plotter(), event.target()]) else {
+            continue;
+        };
+        match (event.kind(), event.outcome()) {
+            (SchemeKind::Assassination, SchemeOutcome::Succeeded) => warn!(
+                "{} {} has {} {} poisoned",
+                plotter.first, plotter.last, target.first, target.last
+            ),
+            (SchemeKind::Seduction, SchemeOutcome::Succeeded) => info!(
+                "{} {} seduces {} {} and they begin an affair",
+                plotter.first, plotter.last, target.first, target.last
+            ),
+            (SchemeKind::Blackmail, SchemeOutcome::Succeeded) => info!(
+                "{} {} blackmails {} {}",
+                plotter.first, plotter.last, target.first, target.last
+            ),
+            (kind, SchemeOutcome::Exposed) => warn!(
+                "{} {}'s {} plot against {} {} is exposed",
+                plotter.first, plotter.last, kind, target.first, target.last
+            ),
+            (kind, SchemeOutcome::Failed) => debug!(
+                "{} {}'s {} scheme against {} {} came to nothing",
+                plotter.first, plotter.last, kind, target.first, target.last
+            ),
+        }
+    }
+}
+
+pub struct SchemePlugin;
+
+impl Plugin for SchemePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SchemeEvent>().add_systems(
+            Update,
+            (
+                hatch_schemes,
+                advance_schemes,
+                resolve_schemes,
+                tell_scheme_stories,
+            )
+                .chain()
+                .run_if(in_state(RunState::Running)),
+        );
+    }
+}
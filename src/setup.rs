@@ -1,146 +1,136 @@
 use crate::{
+    household::{Household, InHousehold},
+    life::{Alive, Deceased},
+    marriage::{Partners, Partnership, PartnershipEnd, Spouse},
     moon::MoonHouse,
-    people::{Children, PersonBundle, Siblings},
-    reproduction::{ChildBearing, Pregnancy, HUMAN_PREGNANCY_LENGTH, HUMAN_PREGNANCY_STD},
-    royalty::{Royalty, Title},
+    naming::{founder_surname, Culture, NameLists, NamingRules},
+    people::PersonBundle,
+    reproduction::{Pregnancy, HUMAN_PREGNANCY_LENGTH, HUMAN_PREGNANCY_STD},
+    royalty::{Legitimacy, Royalty, Title},
+    sex::{Gender, Sex, SexRatios},
+    traits::{Alleles, Gene, Traits},
 };
-use bevy::{prelude::*, utils::HashSet};
+use bevy::prelude::*;
 use rand::prelude::*;
 
-fn create_initial_people(mut commands: Commands) {
-    // TODO: read the lists from file? either way, rethink how we get these names.
-    // create vectors of first names
-    let male_first_names = vec![
-        "Tim", "Tom", "Lachlan", "Alex", "Jack", "Chris", "Harry", "Stephen", "Paul",
-    ];
-    let female_first_names = vec![
-        "Paulina", "Alex", "Lillian", "Pia", "Tess", "Phoebe", "Kim", "Myra", "Claudia",
-    ];
-    // create a vector of last names
-    let mut last_names = vec![
-        "Allan",
-        "Morales",
-        "Miller",
-        "Smith",
-        "Baker",
-        "Tanner",
-        "Peddler",
-        "Freeman",
-        "Butcher",
-        "Carpenter",
-        "Farrier",
-        "Primrose",
-        "Stubbs",
-        "McNaughton",
-        "Morris",
-        "Ploughman",
-        "Bowyer",
-        "Tinker",
-    ];
-
+fn create_initial_people(
+    mut commands: Commands,
+    name_lists: Res<NameLists>,
+    naming_rules: Res<NamingRules>,
+    sex_ratios: Res<SexRatios>,
+) {
     let mut rng = thread_rng();
 
     // create n couples to be the initial people in the kingdom
     for _ in 0..7 {
-        let mut family_male_first_names = male_first_names.clone();
+        let culture: Culture = rng.gen();
+        let names = &name_lists.cultures[&culture];
+
+        let mut family_male_first_names = names.male.clone();
         family_male_first_names.shuffle(&mut rng);
 
-        let mut family_female_first_names = female_first_names.clone();
+        let mut family_female_first_names = names.female.clone();
         family_female_first_names.shuffle(&mut rng);
 
-        let family_name = last_names.pop().unwrap();
+        let mut father_bundle = PersonBundle::initial_people(
+            family_male_first_names.pop().unwrap(),
+            &founder_surname(&name_lists, &naming_rules, culture, Gender::Man, &mut rng),
+            Sex::Male,
+            rand::random::<MoonHouse>(),
+            rng.gen_range(5844..14610),
+        );
+        father_bundle.culture = culture;
 
-        let father = commands
-            .spawn(PersonBundle::initial_people(
-                family_male_first_names.pop().unwrap(),
-                family_name,
-                rand::random::<MoonHouse>(),
-                rng.gen_range(5844..14610),
-            ))
-            .id();
-
-        let mother = commands
-            .spawn((
-                PersonBundle::initial_people(
-                    family_female_first_names.pop().unwrap(),
-                    last_names.pop().unwrap(),
-                    rand::random::<MoonHouse>(),
-                    rng.gen_range(5844..14610),
-                ),
-                ChildBearing,
-            ))
-            .id();
+        let mut mother_bundle = PersonBundle::initial_people(
+            family_female_first_names.pop().unwrap(),
+            &founder_surname(&name_lists, &naming_rules, culture, Gender::Woman, &mut rng),
+            Sex::Female,
+            rand::random::<MoonHouse>(),
+            rng.gen_range(5844..14610),
+        );
+        mother_bundle.culture = culture;
 
+        let father_name = father_bundle.name.clone();
+        let mother_name = mother_bundle.name.clone();
+        let father_traits = father_bundle.traits.clone();
+        let mother_traits = mother_bundle.traits.clone();
+        let father = commands.spawn(father_bundle).id();
+        let mother = commands.spawn(mother_bundle).id();
+
+        let household = marry(&mut commands, father, mother);
         // create a random number of children for the couple
-        let mut children = HashSet::new();
         let mut child_age = rng.gen_range(0..365);
 
         for _ in 1..rng.gen_range(0..8) {
-            let child = if rand::random() {
-                commands
-                    .spawn(PersonBundle::new_child(
-                        family_male_first_names.pop().unwrap(),
-                        family_name,
-                        [father, mother].into(),
-                        [].into(),
-                        rand::random::<MoonHouse>(),
-                        child_age,
-                    ))
-                    .id()
+            let (sex, gender) = sex_ratios.assign(&mut rng);
+            let from_male_names = match gender {
+                Gender::Man => true,
+                Gender::Woman => false,
+                Gender::NonBinary => rng.gen(),
+            };
+            let first_name = if from_male_names {
+                family_male_first_names.pop()
             } else {
-                commands
-                    .spawn((
-                        PersonBundle::new_child(
-                            family_female_first_names.pop().unwrap(),
-                            family_name,
-                            [father, mother].into(),
-                            [].into(),
-                            rand::random::<MoonHouse>(),
-                            child_age,
-                        ),
-                        ChildBearing,
-                    ))
-                    .id()
+                family_female_first_names.pop()
             };
-            // add the child to the HashSet so we can handle them later
-            children.insert(child);
-            child_age += rng.gen_range(365..1460);
-        }
+            let mut child_bundle = PersonBundle::new_child(
+                first_name.unwrap(),
+                &naming_rules.surname(culture, &father_name, &mother_name, gender),
+                sex,
+                [father, mother].into(),
+                rand::random::<MoonHouse>(),
+                child_age,
+            );
+            child_bundle.gender = gender;
+            child_bundle.culture = culture;
+            child_bundle.traits = Traits::inherit(&mother_traits, &father_traits, &mut rng);
 
-        // add the children to the parents
-        commands.entity(father).insert(Children {
-            set: children.clone(),
-        });
-        commands.entity(mother).insert(Children {
-            set: children.clone(),
-        });
-
-        // for each child, insert their siblings
-        for child in &children {
-            let mut siblings = Siblings {
-                set: children.clone(),
-            };
-            siblings.set.remove(child);
-            commands.entity(*child).insert(siblings);
+            commands.spawn((child_bundle, InHousehold { household }));
+            child_age += rng.gen_range(365..1460);
         }
     }
 }
 
+/// Marry a couple and move them into a new shared household
+fn marry(commands: &mut Commands, first: Entity, second: Entity) -> Entity {
+    let household = commands.spawn(Household).id();
+    commands.entity(first).insert((
+        Spouse { entity: second },
+        Partners::married_to(second),
+        InHousehold { household },
+    ));
+    commands.entity(second).insert((
+        Spouse { entity: first },
+        Partners::married_to(first),
+        InHousehold { household },
+    ));
+
+    household
+}
+
 fn add_custom_people(mut commands: Commands) {
     let jack = commands
         .spawn((
-            PersonBundle::initial_people("Jack", "Allan", MoonHouse::Death, 12000),
-            Royalty { title: Title::King },
+            PersonBundle::initial_people("Jack", "Allan", Sex::Male, MoonHouse::Death, 12000),
+            Royalty {
+                title: Title::Monarch,
+            },
+            Legitimacy::default(),
         ))
         .id();
 
     let pau = commands
         .spawn((
-            PersonBundle::initial_people("Paulina", "Morales-Allan", MoonHouse::Storm, 10555),
-            ChildBearing,
+            PersonBundle::initial_people(
+                "Paulina",
+                "Morales-Allan",
+                Sex::Female,
+                MoonHouse::Storm,
+                10555,
+            ),
             Pregnancy::new(HUMAN_PREGNANCY_LENGTH, HUMAN_PREGNANCY_STD, jack),
             Royalty {
-                title: Title::Queen,
+                title: Title::Consort,
             },
         ))
         .id();
@@ -150,8 +140,8 @@ fn add_custom_people(mut commands: Commands) {
             PersonBundle::new_child(
                 "Albert",
                 "Morales-Allan",
+                Sex::Male,
                 [jack, pau].into(),
-                [].into(),
                 MoonHouse::Light,
                 293,
             ),
@@ -165,49 +155,107 @@ fn add_custom_people(mut commands: Commands) {
         .spawn(PersonBundle::new_child(
             "Pip",
             "Morales-Allan",
+            Sex::Female,
             [jack, pau].into(),
-            [].into(),
             MoonHouse::Wild,
             854,
         ))
         .id();
 
-    commands.entity(jack).insert(Children {
-        set: [albie, pip].into(),
-    });
-    commands.entity(pau).insert(Children {
-        set: [albie, pip].into(),
-    });
+    let royal_household = marry(&mut commands, jack, pau);
+    // the queen's children are raised in her own Southron tradition
+    commands.entity(pau).insert(Culture::Southron);
+    for child in [albie, pip] {
+        commands.entity(child).insert((
+            Culture::Southron,
+            InHousehold {
+                household: royal_household,
+            },
+        ));
+    }
 
-    commands
-        .entity(albie)
-        .insert(Siblings { set: [pip].into() });
-    commands.entity(pip).insert(Siblings {
-        set: [albie].into(),
-    });
+    add_royal_traits(&mut commands, jack, pau, [albie, pip]);
+
+    add_wilmots(&mut commands);
+}
+
+/// The royal children take after their parents, Allan nose and all
+fn add_royal_traits(commands: &mut Commands, king: Entity, queen: Entity, children: [Entity; 2]) {
+    let mut rng = thread_rng();
+
+    let mut king_traits = Traits::random(&mut rng);
+    king_traits.genes.insert(
+        Gene::HookedNose,
+        Alleles {
+            maternal: true,
+            paternal: true,
+        },
+    );
+    let queen_traits = Traits::random(&mut rng);
+
+    for child in children {
+        commands
+            .entity(child)
+            .insert(Traits::inherit(&queen_traits, &king_traits, &mut rng));
+    }
+    commands.entity(king).insert(king_traits);
+    commands.entity(queen).insert(queen_traits);
+}
 
+fn add_wilmots(commands: &mut Commands) {
     let jacob = commands
         .spawn(PersonBundle::initial_people(
             "Jacob",
             "Wilmot",
+            Sex::Male,
             MoonHouse::Wind,
             10800,
         ))
         .id();
 
+    // Jacob is a widower, raising Pepsi alone
+    let agnes = commands
+        .spawn(PersonBundle::initial_people(
+            "Agnes",
+            "Wilmot",
+            Sex::Female,
+            MoonHouse::Water,
+            10200,
+        ))
+        .remove::<Alive>()
+        .insert(Deceased)
+        .id();
+
     let pepsi = commands
         .spawn(PersonBundle::new_child(
             "Pepsi",
             "Wilmot",
-            [jacob].into(),
-            [].into(),
+            Sex::Female,
+            [jacob, agnes].into(),
             MoonHouse::Fire,
             7000,
         ))
         .id();
 
-    commands.entity(jacob).insert(Children {
-        set: [pepsi].into(),
+    commands.entity(jacob).insert(Partners {
+        history: vec![Partnership {
+            partner: agnes,
+            ended: Some(PartnershipEnd::Widowed),
+        }],
+    });
+    commands.entity(agnes).insert(Partners {
+        history: vec![Partnership {
+            partner: jacob,
+            ended: Some(PartnershipEnd::Widowed),
+        }],
+    });
+
+    let wilmot_household = commands.spawn(Household).id();
+    commands.entity(jacob).insert(InHousehold {
+        household: wilmot_household,
+    });
+    commands.entity(pepsi).insert(InHousehold {
+        household: wilmot_household,
     });
 }
 
@@ -0,0 +1,89 @@
+use bevy::prelude::*;
+use rand::prelude::*;
+
+/// Biological sex, decides who can bear children
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sex {
+    Male,
+    Female,
+}
+
+impl Sex {
+    pub fn can_bear_children(self) -> bool {
+        self == Sex::Female
+    }
+}
+
+/// Gender identity, decides names and titles
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Gender {
+    Man,
+    Woman,
+    NonBinary,
+}
+
+impl From<Sex> for Gender {
+    fn from(sex: Sex) -> Self {
+        match sex {
+            Sex::Male => Gender::Man,
+            Sex::Female => Gender::Woman,
+        }
+    }
+}
+
+/// How sex and gender are distributed among newborns
+#[derive(Resource, Debug)]
+pub struct SexRatios {
+    /// Chance a baby is born female
+    pub female: f64,
+    /// Chance someone's gender doesn't match their sex
+    pub transgender: f64,
+    /// Chance someone is neither a man nor a woman
+    pub non_binary: f64,
+}
+
+impl Default for SexRatios {
+    fn default() -> Self {
+        SexRatios {
+            female: 0.49,
+            transgender: 0.01,
+            non_binary: 0.01,
+        }
+    }
+}
+
+impl SexRatios {
+    pub fn assign(&self, rng: &mut ThreadRng) -> (Sex, Gender) {
+        let sex = self.sex(rng);
+        (sex, self.gender(sex, rng))
+    }
+
+    pub fn sex(&self, rng: &mut ThreadRng) -> Sex {
+        if rng.gen_bool(self.female) {
+            Sex::Female
+        } else {
+            Sex::Male
+        }
+    }
+
+    pub fn gender(&self, sex: Sex, rng: &mut ThreadRng) -> Gender {
+        if rng.gen_bool(self.non_binary) {
+            Gender::NonBinary
+        } else if rng.gen_bool(self.transgender) {
+            match sex {
+                Sex::Male => Gender::Woman,
+                Sex::Female => Gender::Man,
+            }
+        } else {
+            sex.into()
+        }
+    }
+}
+
+pub struct SexPlugin;
+
+impl Plugin for SexPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SexRatios>();
+    }
+}
@@ -0,0 +1,307 @@
+use bevy::{prelude::*, utils::HashSet};
+use rand::prelude::*;
+
+use crate::{
+    age::Age,
+    crime::{Exiled, Imprisoned},
+    household::InHousehold,
+    kinship::Kinship,
+    life::Alive,
+    marriage::Spouse,
+    naming::Culture,
+    people::Name,
+    personality::Personality,
+    relationships::{OpinionEvent, Relationships},
+    state::RunState,
+};
+
+/// Chance each day that someone goes looking for company
+const DAILY_INTERACTION_CHANCE: f64 = 0.1;
+/// Babies don't have much to say
+const SOCIAL_AGE: i32 = 3;
+const FLIRTING_AGE: i32 = 16;
+const HOUSEHOLD_WEIGHT: f64 = 8.0;
+const KIN_WEIGHT: f64 = 4.0;
+/// Sharing a culture stands in for living nearby
+const CULTURE_WEIGHT: f64 = 1.0;
+/// How much more likely people are to seek out someone they like, per point of opinion
+const OPINION_WEIGHT: f64 = 0.04;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interaction {
+    Chat,
+    Argument,
+    Gift,
+    Flirtation {
+        welcome: bool,
+    },
+    Insult,
+    /// Made off with something of theirs
+    Theft,
+}
+
+impl Interaction {
+    /// How the initiator and the target feel about each other afterwards
+    fn opinion_changes(self) -> (i32, i32) {
+        match self {
+            Interaction::Chat => (2, 2),
+            Interaction::Argument => (-5, -5),
+            Interaction::Gift => (3, 8),
+            Interaction::Flirtation { welcome: true } => (10, 10),
+            Interaction::Flirtation { welcome: false } => (0, -5),
+            Interaction::Insult => (-2, -12),
+            Interaction::Theft => (0, -15),
+        }
+    }
+
+    fn reason(self) -> &'static str {
+        match self {
+            Interaction::Chat => "a pleasant chat",
+            Interaction::Argument => "an argument",
+            Interaction::Gift => "a gift",
+            Interaction::Flirtation { welcome: true } => "a flirtation",
+            Interaction::Flirtation { welcome: false } => "an unwelcome advance",
+            Interaction::Insult => "an insult",
+            Interaction::Theft => "a theft",
+        }
+    }
+
+    /// Whether it's worth telling anyone about
+    fn notable(self) -> bool {
+        !matches!(self, Interaction::Chat | Interaction::Argument)
+    }
+}
+
+/// Something worth gossiping about happened between two people
+#[derive(Event, Debug)]
+pub struct SocialEvent {
+    initiator: Entity,
+    target: Entity,
+    interaction: Interaction,
+}
+
+impl SocialEvent {
+    pub fn new(initiator: Entity, target: Entity, interaction: Interaction) -> Self {
+        SocialEvent {
+            initiator,
+            target,
+            interaction,
+        }
+    }
+
+    pub fn initiator(&self) -> Entity {
+        self.initiator
+    }
+
+    pub fn target(&self) -> Entity {
+        self.target
+    }
+
+    pub fn interaction(&self) -> Interaction {
+        self.interaction
+    }
+}
+
+/// Who's who, as far as deciding who to talk to goes
+struct Socialite<'a> {
+    entity: Entity,
+    personality: &'a Personality,
+    age: i32,
+    household: Option<Entity>,
+    culture: Culture,
+    /// Parents, children, siblings and spouse
+    kin: HashSet<Entity>,
+    spouse: Option<Entity>,
+}
+
+impl Socialite<'_> {
+    fn is_kin(&self, other: Entity) -> bool {
+        self.kin.contains(&other)
+    }
+}
+
+/// How likely `from` is to seek out `to` today
+fn company_weight(from: &Socialite, to: &Socialite, relationships: &Relationships) -> f64 {
+    let mut weight = 1.0;
+    if from.household.is_some() && from.household == to.household {
+        weight += HOUSEHOLD_WEIGHT;
+    }
+    if from.is_kin(to.entity) {
+        weight += KIN_WEIGHT;
+    }
+    if from.culture == to.culture {
+        weight += CULTURE_WEIGHT;
+    }
+    let opinion = f64::from(relationships.opinion(from.entity, to.entity));
+    (weight * (1.0 + opinion * OPINION_WEIGHT)).max(0.1)
+}
+
+/// What the initiator does, driven by their personality and what they think of the target
+fn choose_interaction(
+    from: &Socialite,
+    to: &Socialite,
+    relationships: &Relationships,
+    rng: &mut ThreadRng,
+) -> Interaction {
+    let opinion = f64::from(relationships.opinion(from.entity, to.entity)) / 100.0;
+    let liking = opinion.max(0.0);
+    let disliking = (-opinion).max(0.0);
+    let personality = from.personality;
+
+    // married people only stray if they're lusty and disloyal
+    let faithfulness = match from.spouse {
+        Some(spouse) if spouse == to.entity => 0.0,
+        Some(_) => personality.loyalty,
+        None => 0.0,
+    };
+    let can_flirt = from.age >= FLIRTING_AGE
+        && to.age >= FLIRTING_AGE
+        && (!from.is_kin(to.entity) || from.spouse == Some(to.entity));
+    let flirting = if can_flirt {
+        personality.lust * (1.0 - faithfulness) * (0.5 + liking)
+    } else {
+        0.0
+    };
+
+    let options = [
+        (Interaction::Chat, 1.0 + personality.loyalty),
+        (
+            Interaction::Argument,
+            personality.ambition * (0.3 + disliking),
+        ),
+        (
+            Interaction::Gift,
+            (personality.piety + personality.loyalty) * 0.5 * liking,
+        ),
+        (Interaction::Flirtation { welcome: true }, flirting),
+        (Interaction::Insult, personality.cruelty * (0.2 + disliking)),
+        (
+            Interaction::Theft,
+            personality.ambition
+                * (1.0 - personality.loyalty)
+                * (1.0 - personality.piety)
+                * (0.05 + disliking * 0.1),
+        ),
+    ];
+    let interaction = options
+        .choose_weighted(rng, |(_, weight)| *weight)
+        .map_or(Interaction::Chat, |(interaction, _)| *interaction);
+
+    if let Interaction::Flirtation { .. } = interaction {
+        // an advance lands if the target is keen on them, or just keen
+        let target_opinion = f64::from(relationships.opinion(to.entity, from.entity)) / 100.0;
+        let receptiveness = (to.personality.lust + target_opinion).clamp(0.05, 0.95);
+        Interaction::Flirtation {
+            welcome: rng.gen_bool(receptiveness),
+        }
+    } else {
+        interaction
+    }
+}
+
+/// Each day some people seek out someone to spend time with
+fn socialise(
+    relationships: Res<Relationships>,
+    mut ev_opinion: EventWriter<OpinionEvent>,
+    mut ev_social: EventWriter<SocialEvent>,
+    kinship: Kinship,
+    query: Query<
+        (
+            Entity,
+            &Personality,
+            &Age,
+            Option<&InHousehold>,
+            &Culture,
+            Option<&Spouse>,
+        ),
+        (With<Alive>, Without<Imprisoned>, Without<Exiled>),
+    >,
+) {
+    let mut rng = thread_rng();
+
+    let people: Vec<Socialite> = query
+        .iter()
+        .filter(|(_, _, age, _, _, _)| age.years() >= SOCIAL_AGE)
+        .map(
+            |(entity, personality, age, household, culture, spouse)| Socialite {
+                entity,
+                personality,
+                age: age.years(),
+                household: household.map(|household| household.household),
+                culture: *culture,
+                kin: kinship.close_kin(entity),
+                spouse: spouse.map(|spouse| spouse.entity),
+            },
+        )
+        .collect();
+
+    for from in &people {
+        if !rng.gen_bool(DAILY_INTERACTION_CHANCE) {
+            continue;
+        }
+
+        let others: Vec<&Socialite> = people
+            .iter()
+            .filter(|other| other.entity != from.entity)
+            .collect();
+        let Ok(to) =
+            others.choose_weighted(&mut rng, |to| company_weight(from, to, &relationships))
+        else {
+            continue;
+        };
+
+        let interaction = choose_interaction(from, to, &relationships, &mut rng);
+        let (from_change, to_change) = interaction.opinion_changes();
+        ev_opinion.send(OpinionEvent::new(
+            from.entity,
+            to.entity,
+            from_change,
+            interaction.reason(),
+        ));
+        ev_opinion.send(OpinionEvent::new(
+            to.entity,
+            from.entity,
+            to_change,
+            interaction.reason(),
+        ));
+
+        if interaction.notable() {
+            ev_social.send(SocialEvent::new(from.entity, to.entity, interaction));
+        }
+    }
+}
+
+/// Spread the gossip
+fn tell_social_stories(mut ev_social: EventReader<SocialEvent>, query: Query<&Name>) {
+    for event in ev_social.read() {
+        let Ok([initiator, target]) = query.get_many([event.initiator, event.target]) else {
+            continue;
+        };
+        let what_happened = match event.interaction {
+            Interaction::Chat => "chatted with",
+            Interaction::Argument => "argued with",
+            Interaction::Gift => "gave a gift to",
+            Interaction::Flirtation { welcome: true } => "flirted with",
+            Interaction::Flirtation { welcome: false } => "was rebuffed by",
+            Interaction::Insult => "insulted",
+            Interaction::Theft => "stole from",
+        };
+        info!(
+            "{} {} {} {} {}",
+            initiator.first, initiator.last, what_happened, target.first, target.last
+        );
+    }
+}
+
+pub struct SocialPlugin;
+
+impl Plugin for SocialPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SocialEvent>().add_systems(
+            Update,
+            (socialise, tell_social_stories)
+                .chain()
+                .run_if(in_state(RunState::Running)),
+        );
+    }
+}
@@ -0,0 +1,459 @@
+use std::{cmp::Reverse, fmt};
+
+use bevy::{ecs::system::SystemParam, prelude::*, utils::HashSet};
+
+use crate::{
+    age::Age,
+    crime::Exiled,
+    kinship::Kinship,
+    life::{distinct_deaths, Alive, DeathEvent},
+    people::Name,
+    personality::Personality,
+    relationships::Relationships,
+    royalty::{Legitimacy, Royalty, Title, UNCROWNED_LEGITIMACY},
+    sex::Gender,
+    state::RunState,
+    traits::{Attribute, Traits},
+};
+
+/// How far back the family tree to look for a claimant
+const MAX_SUCCESSION_DEPTH: u32 = 8;
+/// How far back a shared ancestor makes someone part of the royal family
+const DYNASTY_DEPTH: u32 = 2;
+/// Old enough to be chosen by the family or the electors
+const ADULT_AGE: i32 = 16;
+
+/// How the next monarch is chosen when the current one dies
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SuccessionLaw {
+    /// Eldest child first, sons before daughters, then their children, then back up the tree
+    MalePreferencePrimogeniture,
+    /// Eldest child first regardless of gender
+    AbsolutePrimogeniture,
+    /// The royals vote for whichever adult of the family they think best of
+    Elective,
+    /// The family picks its most capable adult
+    Tanistry,
+    /// The oldest living member of the family
+    Seniority,
+}
+
+impl SuccessionLaw {
+    pub const ALL: [SuccessionLaw; 5] = [
+        SuccessionLaw::MalePreferencePrimogeniture,
+        SuccessionLaw::AbsolutePrimogeniture,
+        SuccessionLaw::Elective,
+        SuccessionLaw::Tanistry,
+        SuccessionLaw::Seniority,
+    ];
+}
+
+impl fmt::Display for SuccessionLaw {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let law = match self {
+            SuccessionLaw::MalePreferencePrimogeniture => "male-preference primogeniture",
+            SuccessionLaw::AbsolutePrimogeniture => "absolute primogeniture",
+            SuccessionLaw::Elective => "election",
+            SuccessionLaw::Tanistry => "tanistry",
+            SuccessionLaw::Seniority => "seniority",
+        };
+        write!(f, "{law}")
+    }
+}
+
+#[derive(Resource, Debug)]
+pub struct SuccessionRules {
+    pub law: SuccessionLaw,
+}
+
+impl Default for SuccessionRules {
+    fn default() -> Self {
+        SuccessionRules {
+            law: SuccessionLaw::MalePreferencePrimogeniture,
+        }
+    }
+}
+
+/// The crown has passed from one monarch to the next
+#[derive(Event, Debug)]
+pub struct SuccessionEvent {
+    predecessor: Entity,
+    heir: Entity,
+    contested: bool,
+}
+
+impl SuccessionEvent {
+    pub fn new(predecessor: Entity, heir: Entity) -> Self {
+        SuccessionEvent {
+            predecessor,
+            heir,
+            contested: false,
+        }
+    }
+
+    /// The crown was won in a succession crisis rather than passed on by law
+    pub fn contested(predecessor: Entity, heir: Entity) -> Self {
+        SuccessionEvent {
+            predecessor,
+            heir,
+            contested: true,
+        }
+    }
+
+    pub fn predecessor(&self) -> Entity {
+        self.predecessor
+    }
+
+    pub fn heir(&self) -> Entity {
+        self.heir
+    }
+
+    pub fn is_contested(&self) -> bool {
+        self.contested
+    }
+}
+
+/// Everything needed to work out who has a claim to the throne
+#[derive(SystemParam)]
+pub struct Claimants<'w, 's> {
+    kinship: Kinship<'w, 's>,
+    relationships: Res<'w, Relationships>,
+    query: Query<
+        'w,
+        's,
+        (
+            &'static Age,
+            &'static Gender,
+            &'static Personality,
+            &'static Traits,
+            Has<Alive>,
+            Has<Exiled>,
+        ),
+    >,
+    query_royals: Query<'w, 's, Entity, (With<Royalty>, With<Alive>)>,
+}
+
+impl<'w, 's> Claimants<'w, 's> {
+    pub fn kinship(&self) -> &Kinship<'w, 's> {
+        &self.kinship
+    }
+
+    pub fn opinion(&self, from: Entity, to: Entity) -> i32 {
+        self.relationships.opinion(from, to)
+    }
+
+    pub fn is_alive(&self, person: Entity) -> bool {
+        self.query
+            .get(person)
+            .is_ok_and(|(_, _, _, _, alive, _)| alive)
+    }
+
+    /// Alive and still in the realm, the exiled lose any claim they had
+    pub fn can_claim(&self, person: Entity) -> bool {
+        self.query
+            .get(person)
+            .is_ok_and(|(_, _, _, _, alive, exiled)| alive && !exiled)
+    }
+
+    pub fn is_adult(&self, person: Entity) -> bool {
+        self.is_of_age(person, ADULT_AGE)
+    }
+
+    pub fn is_of_age(&self, person: Entity, years: i32) -> bool {
+        self.query
+            .get(person)
+            .is_ok_and(|(age, ..)| age.years() >= years)
+    }
+
+    /// Who would inherit under the given law, if anyone is left to
+    pub fn heir(&self, monarch: Entity, law: SuccessionLaw) -> Option<Entity> {
+        self.ranked(monarch, law).first().copied()
+    }
+
+    /// Everyone with a claim under the given law, strongest claim first
+    pub fn ranked(&self, monarch: Entity, law: SuccessionLaw) -> Vec<Entity> {
+        match law {
+            SuccessionLaw::MalePreferencePrimogeniture => self.line_of_succession(monarch, true),
+            SuccessionLaw::AbsolutePrimogeniture => self.line_of_succession(monarch, false),
+            SuccessionLaw::Elective => {
+                let candidates: Vec<Entity> = self
+                    .dynasty(monarch)
+                    .into_iter()
+                    .filter(|person| self.is_adult(*person))
+                    .collect();
+                self.tally(monarch, &candidates)
+                    .into_iter()
+                    .map(|(candidate, _)| candidate)
+                    .collect()
+            }
+            SuccessionLaw::Tanistry => {
+                let mut candidates: Vec<Entity> = self
+                    .dynasty(monarch)
+                    .into_iter()
+                    .filter(|person| self.is_adult(*person))
+                    .collect();
+                candidates.sort_by(|a, b| self.worthiness(*b).total_cmp(&self.worthiness(*a)));
+                candidates
+            }
+            SuccessionLaw::Seniority => {
+                let mut candidates: Vec<Entity> = self.dynasty(monarch).into_iter().collect();
+                candidates.sort_by_key(|person| {
+                    Reverse(self.query.get(*person).map_or(0, |(age, ..)| age.days()))
+                });
+                candidates
+            }
+        }
+    }
+
+    /// Everyone living in the realm in order of precedence: the monarch's descendants, then their
+    /// parents' and so on up
+    pub fn line_of_succession(&self, monarch: Entity, male_preference: bool) -> Vec<Entity> {
+        let mut line = Vec::new();
+        let mut visited = HashSet::from([monarch]);
+        self.follow_line(monarch, male_preference, &mut line, &mut visited);
+
+        let ancestors = self.kinship.ancestors(monarch, MAX_SUCCESSION_DEPTH);
+        for generation in 1..=MAX_SUCCESSION_DEPTH {
+            let mut elders: Vec<Entity> = ancestors
+                .iter()
+                .filter(|(_, distance)| **distance == generation)
+                .map(|(ancestor, _)| *ancestor)
+                .collect();
+            self.order_by_precedence(&mut elders, male_preference);
+
+            for elder in elders {
+                if visited.insert(elder) && self.can_claim(elder) {
+                    line.push(elder);
+                }
+                self.follow_line(elder, male_preference, &mut line, &mut visited);
+            }
+        }
+
+        line
+    }
+
+    /// Depth first through someone's children, eldest line first
+    fn follow_line(
+        &self,
+        person: Entity,
+        male_preference: bool,
+        line: &mut Vec<Entity>,
+        visited: &mut HashSet<Entity>,
+    ) {
+        let mut children: Vec<Entity> = self.kinship.children(person).into_iter().collect();
+        self.order_by_precedence(&mut children, male_preference);
+
+        for child in children {
+            if !visited.insert(child) {
+                continue;
+            }
+            if self.can_claim(child) {
+                line.push(child);
+            }
+            self.follow_line(child, male_preference, line, visited);
+        }
+    }
+
+    /// Eldest first, and men before women if the law says so. The dead stop ageing, so this is
+    /// only a best guess for someone who died long ago.
+    fn order_by_precedence(&self, people: &mut [Entity], male_preference: bool) {
+        people.sort_by_key(|person| {
+            self.query
+                .get(*person)
+                .map_or((true, Reverse(0)), |(age, gender, ..)| {
+                    (
+                        male_preference && *gender != Gender::Man,
+                        Reverse(age.days()),
+                    )
+                })
+        });
+    }
+
+    /// Living descendants of the monarch's grandparents who haven't been exiled, the family the
+    /// throne stays in
+    pub fn dynasty(&self, monarch: Entity) -> HashSet<Entity> {
+        let mut founders: Vec<Entity> = self
+            .kinship
+            .ancestors(monarch, DYNASTY_DEPTH)
+            .into_keys()
+            .collect();
+        founders.push(monarch);
+
+        founders
+            .iter()
+            .flat_map(|founder| {
+                self.kinship
+                    .descendants(*founder, MAX_SUCCESSION_DEPTH)
+                    .into_keys()
+                    .chain([*founder])
+            })
+            .filter(|person| *person != monarch && self.can_claim(*person))
+            .collect()
+    }
+
+    /// How fit someone is to rule, from their talents and drive, from 0 to 4
+    pub fn worthiness(&self, person: Entity) -> f64 {
+        self.query
+            .get(person)
+            .map_or(0.0, |(_, _, personality, traits, ..)| {
+                traits.attribute(Attribute::Wit)
+                    + traits.attribute(Attribute::Swordplay)
+                    + traits.attribute(Attribute::Strength)
+                    + personality.ambition
+            })
+    }
+
+    /// The living royals and the candidates themselves each vote for the candidate they like best.
+    /// Ties go to whoever is more worthy.
+    pub fn vote(&self, monarch: Entity, candidates: &[Entity]) -> Option<Entity> {
+        self.tally(monarch, candidates)
+            .first()
+            .map(|(winner, _)| *winner)
+    }
+
+    /// Every candidate's votes, most first
+    fn tally(&self, monarch: Entity, candidates: &[Entity]) -> Vec<(Entity, u32)> {
+        let mut electors: HashSet<Entity> = self
+            .query_royals
+            .iter()
+            .filter(|elector| *elector != monarch)
+            .collect();
+        electors.extend(candidates.iter().copied());
+
+        let mut votes: Vec<(Entity, u32)> = candidates.iter().map(|c| (*c, 0)).collect();
+        for elector in electors {
+            let favourite = votes.iter_mut().max_by(|(a, _), (b, _)| {
+                self.relationships
+                    .opinion(elector, *a)
+                    .cmp(&self.relationships.opinion(elector, *b))
+                    .then(self.worthiness(*a).total_cmp(&self.worthiness(*b)))
+            });
+            if let Some((_, count)) = favourite {
+                *count += 1;
+            }
+        }
+
+        votes.sort_by(|(a, a_votes), (b, b_votes)| {
+            b_votes
+                .cmp(a_votes)
+                .then(self.worthiness(*b).total_cmp(&self.worthiness(*a)))
+        });
+        votes
+    }
+
+    /// Make `heir` the monarch and title their family. A dead monarch's widow becomes dowager,
+    /// a deposed monarch and their consort lose their titles. The old monarch's children are no
+    /// longer princes unless they're the new monarch's siblings or children.
+    pub fn crown(&self, commands: &mut Commands, heir: Entity, predecessor: Entity) {
+        let deposed = self.is_alive(predecessor);
+        if deposed {
+            commands
+                .entity(predecessor)
+                .remove::<(Royalty, Legitimacy)>();
+        }
+        if let Some(widow) = self.kinship.spouse(predecessor) {
+            if widow != heir && self.can_claim(widow) {
+                if deposed {
+                    commands.entity(widow).remove::<Royalty>();
+                } else {
+                    commands.entity(widow).insert(Royalty {
+                        title: Title::Dowager,
+                    });
+                }
+            }
+        }
+
+        let heirs_family: HashSet<Entity> = self
+            .kinship
+            .siblings(heir)
+            .into_iter()
+            .chain(self.kinship.children(heir))
+            .chain(self.kinship.spouse(heir))
+            .chain([heir])
+            .collect();
+        for child in self.kinship.children(predecessor) {
+            if !heirs_family.contains(&child) {
+                commands.entity(child).remove::<Royalty>();
+            }
+        }
+
+        commands.entity(heir).insert((
+            Royalty {
+                title: Title::Monarch,
+            },
+            Legitimacy {
+                score: UNCROWNED_LEGITIMACY,
+            },
+        ));
+        if let Some(spouse) = self.kinship.spouse(heir) {
+            commands.entity(spouse).insert(Royalty {
+                title: Title::Consort,
+            });
+        }
+        for child in self.kinship.children(heir) {
+            if self.can_claim(child) {
+                commands.entity(child).insert(Royalty {
+                    title: Title::Prince,
+                });
+            }
+        }
+    }
+}
+
+/// When a monarch dies the crown passes on, and the new royal family is titled
+fn handle_succession(
+    mut commands: Commands,
+    mut ev_death: EventReader<DeathEvent>,
+    mut ev_succession: EventWriter<SuccessionEvent>,
+    rules: Res<SuccessionRules>,
+    claimants: Claimants,
+    query_royals: Query<(&Name, &Royalty)>,
+    query_names: Query<(&Name, &Gender)>,
+) {
+    for event in distinct_deaths(&mut ev_death) {
+        let dying = event.dying();
+        let Ok((name, royalty)) = query_royals.get(dying) else {
+            continue;
+        };
+        commands.entity(dying).remove::<Royalty>();
+        if royalty.title != Title::Monarch {
+            continue;
+        }
+
+        let Some(heir) = claimants.heir(dying, rules.law) else {
+            warn!(
+                "{} {} died with no heir, the throne lies empty",
+                name.first, name.last
+            );
+            continue;
+        };
+
+        claimants.crown(&mut commands, heir, dying);
+
+        if let Ok((heir_name, gender)) = query_names.get(heir) {
+            info!(
+                "By {} the crown passes from {} {}. Long live {} {} {}!",
+                rules.law,
+                name.first,
+                name.last,
+                Title::Monarch.styled(*gender),
+                heir_name.first,
+                heir_name.last
+            );
+        }
+        ev_succession.send(SuccessionEvent::new(dying, heir));
+    }
+}
+
+pub struct SuccessionPlugin;
+
+impl Plugin for SuccessionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SuccessionRules>()
+            .add_event::<SuccessionEvent>()
+            .add_systems(
+                Update,
+                handle_succession.run_if(in_state(RunState::Running)),
+            );
+    }
+}
@@ -0,0 +1,465 @@
+use std::fmt::{self, Write};
+
+use bevy::prelude::*;
+
+use crate::{
+    age::Age,
+    calendar::{Calendar, Date, Reign},
+    dynasty::{Dynasty, InDynasty},
+    life::{distinct_deaths, Alive, DeathEvent},
+    naming::Culture,
+    people::{Name, Parents},
+    royalty::{Royalty, Title},
+    sex::Gender,
+    state::RunState,
+    succession::{Claimants, SuccessionEvent, SuccessionLaw, SuccessionRules},
+};
+
+const KINGDOM_NAME: &str = "the Kingdom";
+const EXPORT_PATH: &str = "monarchs.txt";
+/// Old enough to be granted lands when the realm is founded
+const LANDED_AGE: i32 = 16;
+
+/// The duchies of the realm, one for each culture outside the heartland, with their counties
+const DUCHIES: [(Culture, &str, [&str; 2]); 3] = [
+    (Culture::Northern, "Northmarch", ["Frostholm", "Ravensgate"]),
+    (Culture::Islander, "the Isles", ["Saltcliff", "Gullhaven"]),
+    (Culture::Southron, "Southreach", ["Sunmere", "Dustfold"]),
+];
+/// Counties held directly from the crown
+const CROWN_COUNTIES: [&str; 2] = ["Kingsbridge", "Elmsworth"];
+
+/// How high a landed title ranks, and what its holder is called
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TitleRank {
+    Kingdom,
+    Duchy,
+    County,
+}
+
+impl TitleRank {
+    pub fn styled(self, gender: Gender) -> &'static str {
+        use Gender::{Man, NonBinary, Woman};
+        match (self, gender) {
+            (TitleRank::Kingdom, _) => Title::Monarch.styled(gender),
+            (TitleRank::Duchy, Man) => "Duke",
+            (TitleRank::Duchy, Woman) => "Duchess",
+            (TitleRank::Duchy, NonBinary) => "Peer",
+            (TitleRank::County, Man | NonBinary) => "Count",
+            (TitleRank::County, Woman) => "Countess",
+        }
+    }
+}
+
+impl fmt::Display for TitleRank {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rank = match self {
+            TitleRank::Kingdom => "Kingdom",
+            TitleRank::Duchy => "Duchy",
+            TitleRank::County => "County",
+        };
+        write!(f, "{rank}")
+    }
+}
+
+/// How someone came to hold a title
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Acquisition {
+    /// Granted when the realm was divided up
+    Founding,
+    /// Passed down from the last holder
+    Inheritance,
+    /// Came to the throne under the succession law
+    Succession(SuccessionLaw),
+    /// Returned to the liege when the last holder's line died out
+    Escheat,
+    /// Won by a claimant in a succession crisis
+    Crisis,
+}
+
+impl fmt::Display for Acquisition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Acquisition::Founding => write!(f, "granted at the founding of the realm"),
+            Acquisition::Inheritance => write!(f, "inherited"),
+            Acquisition::Succession(law) => write!(f, "succeeded by {law}"),
+            Acquisition::Escheat => write!(f, "returned to the liege"),
+            Acquisition::Crisis => write!(f, "won in a succession crisis"),
+        }
+    }
+}
+
+/// Someone's time holding a title
+#[derive(Debug, Clone)]
+pub struct Tenure {
+    pub holder: Entity,
+    /// The holder's first name, which regnal numbers are counted by
+    pub regnal_name: String,
+    pub from: Date,
+    pub acquired: Acquisition,
+    pub until: Option<Date>,
+    /// How the tenure ended, e.g. "died of childbirth"
+    pub lost: Option<String>,
+}
+
+/// Roman numerals for regnal numbers
+fn roman(mut n: usize) -> String {
+    const NUMERALS: [(usize, &str); 9] = [
+        (100, "C"),
+        (90, "XC"),
+        (50, "L"),
+        (40, "XL"),
+        (10, "X"),
+        (9, "IX"),
+        (5, "V"),
+        (4, "IV"),
+        (1, "I"),
+    ];
+    let mut numeral = String::new();
+    for (value, letters) in NUMERALS {
+        while n >= value {
+            numeral.push_str(letters);
+            n -= value;
+        }
+    }
+    numeral
+}
+
+/// A piece of the realm that can be held, e.g. the Duchy of Northmarch
+#[derive(Component, Debug)]
+pub struct LandedTitle {
+    pub name: String,
+    pub rank: TitleRank,
+    pub holder: Option<Entity>,
+    /// The title this one belongs to by right, e.g. a county's duchy
+    pub liege: Option<Entity>,
+    /// Every tenure of the title, oldest first
+    pub history: Vec<Tenure>,
+}
+
+impl LandedTitle {
+    pub fn new(name: &str, rank: TitleRank, liege: Option<Entity>) -> Self {
+        LandedTitle {
+            name: name.into(),
+            rank,
+            holder: None,
+            liege,
+            history: Vec::new(),
+        }
+    }
+
+    /// Hand the title to someone new, closing the old holder's tenure if it's still open
+    pub fn grant(&mut self, holder: Entity, name: &str, date: Date, acquired: Acquisition) {
+        self.vacate(date, "was displaced");
+        self.holder = Some(holder);
+        self.history.push(Tenure {
+            holder,
+            regnal_name: name.into(),
+            from: date,
+            acquired,
+            until: None,
+            lost: None,
+        });
+    }
+
+    /// The holder loses the title and it's left empty for now
+    pub fn vacate(&mut self, date: Date, lost: &str) {
+        self.holder = None;
+        if let Some(tenure) = self.history.last_mut() {
+            if tenure.until.is_none() {
+                tenure.until = Some(date);
+                tenure.lost = Some(lost.into());
+            }
+        }
+    }
+
+    /// The holder of a tenure with a regnal number if anyone else has held the title under
+    /// the same name, e.g. "Jack II"
+    pub fn regnal_name(&self, tenure: &Tenure) -> String {
+        let namesakes: Vec<Entity> = self
+            .history
+            .iter()
+            .filter(|other| other.regnal_name == tenure.regnal_name)
+            .map(|other| other.holder)
+            .fold(Vec::new(), |mut holders, holder| {
+                if !holders.contains(&holder) {
+                    holders.push(holder);
+                }
+                holders
+            });
+        if namesakes.len() < 2 {
+            return tenure.regnal_name.clone();
+        }
+        let number = namesakes
+            .iter()
+            .position(|holder| *holder == tenure.holder)
+            .map_or(1, |at| at + 1);
+        format!("{} {}", tenure.regnal_name, roman(number))
+    }
+
+    /// The open tenure, if the title is held
+    pub fn current(&self) -> Option<&Tenure> {
+        self.history.last().filter(|tenure| tenure.until.is_none())
+    }
+}
+
+impl fmt::Display for LandedTitle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.rank == TitleRank::Kingdom {
+            write!(f, "{}", self.name)
+        } else {
+            write!(f, "{} of {}", self.rank, self.name)
+        }
+    }
+}
+
+/// Carve the realm into titles and grant them: the kingdom to the monarch, and the duchies and
+/// counties to the eldest founders of each culture
+fn found_realm(
+    mut commands: Commands,
+    query_calendar: Query<&Calendar>,
+    query_royals: Query<(Entity, &Royalty)>,
+    query_people: Query<(Entity, &Age, &Culture, &Parents), (With<Alive>, Without<Royalty>)>,
+    query_names: Query<&Name>,
+) {
+    let date = query_calendar.single().date();
+    let monarch = query_royals
+        .iter()
+        .find(|(_, royalty)| royalty.title == Title::Monarch)
+        .map(|(monarch, _)| monarch);
+
+    let mut spawn_title = |name: &str, rank, liege, holder: Option<Entity>| {
+        let mut title = LandedTitle::new(name, rank, liege);
+        if let Some((holder, name)) =
+            holder.and_then(|holder| Some((holder, query_names.get(holder).ok()?)))
+        {
+            title.grant(holder, &name.first, date, Acquisition::Founding);
+        }
+        commands.spawn(title).id()
+    };
+    let nobles_of = |culture: Culture| {
+        let mut nobles: Vec<(Entity, i32)> = query_people
+            .iter()
+            .filter(|(_, age, person_culture, parents)| {
+                **person_culture == culture && parents.is_empty() && age.years() >= LANDED_AGE
+            })
+            .map(|(person, age, _, _)| (person, age.days()))
+            .collect();
+        nobles.sort_by_key(|(_, days)| std::cmp::Reverse(*days));
+        nobles.into_iter().map(|(person, _)| person)
+    };
+
+    let kingdom = spawn_title(KINGDOM_NAME, TitleRank::Kingdom, None, monarch);
+    let mut crown_nobles = nobles_of(Culture::Kingdom);
+    for county in CROWN_COUNTIES {
+        let holder = crown_nobles.next().or(monarch);
+        spawn_title(county, TitleRank::County, Some(kingdom), holder);
+    }
+
+    for (culture, duchy_name, counties) in DUCHIES {
+        let mut nobles = nobles_of(culture);
+        let duke = nobles.next().or(monarch);
+        let duchy = spawn_title(duchy_name, TitleRank::Duchy, Some(kingdom), duke);
+        for county in counties {
+            let holder = nobles.next().or(duke);
+            spawn_title(county, TitleRank::County, Some(duchy), holder);
+        }
+    }
+    debug!("The realm has been divided into titles");
+}
+
+/// Titles pass on when their holder dies. The kingdom follows the succession law, lesser titles
+/// go to the next in line by primogeniture, or back to their liege if the line has died out.
+fn inherit_titles(
+    mut ev_death: EventReader<DeathEvent>,
+    mut ev_succession: EventReader<SuccessionEvent>,
+    rules: Res<SuccessionRules>,
+    claimants: Claimants,
+    query_calendar: Query<&Calendar>,
+    mut query_titles: Query<(Entity, &mut LandedTitle)>,
+    query_names: Query<&Name>,
+) {
+    let date = query_calendar.single().date();
+    let male_preference = rules.law == SuccessionLaw::MalePreferencePrimogeniture;
+
+    for event in distinct_deaths(&mut ev_death) {
+        let dying = event.dying();
+        // most of the dead hold nothing, so don't work out an heir unless there's something to inherit
+        if !query_titles
+            .iter()
+            .any(|(_, title)| title.holder == Some(dying))
+        {
+            continue;
+        }
+        let mut heir = None;
+        let lost = format!("died of {}", event.cause().to_lowercase());
+
+        let lieges: Vec<(Entity, Option<Entity>)> = query_titles
+            .iter()
+            .map(|(title, details)| (title, details.holder))
+            .collect();
+        for (_, mut title) in &mut query_titles {
+            if title.holder != Some(dying) {
+                continue;
+            }
+            title.vacate(date, &lost);
+            // the crown waits for the succession to decide who gets it
+            if title.rank == TitleRank::Kingdom {
+                continue;
+            }
+
+            let successor = heir
+                .get_or_insert_with(|| {
+                    claimants
+                        .line_of_succession(dying, male_preference)
+                        .first()
+                        .copied()
+                })
+                .map(|heir| (heir, Acquisition::Inheritance))
+                .or_else(|| {
+                    lieges
+                        .iter()
+                        .find(|(liege, _)| Some(*liege) == title.liege)
+                        .and_then(|(_, holder)| *holder)
+                        .filter(|holder| *holder != dying)
+                        .map(|holder| (holder, Acquisition::Escheat))
+                });
+            let Some((successor, acquired)) = successor else {
+                continue;
+            };
+            if let Ok(name) = query_names.get(successor) {
+                title.grant(successor, &name.first, date, acquired);
+                info!("The {} passes to {} {}", *title, name.first, name.last);
+            }
+        }
+    }
+
+    for event in ev_succession.read() {
+        let Ok(name) = query_names.get(event.heir()) else {
+            continue;
+        };
+        let acquired = if event.is_contested() {
+            Acquisition::Crisis
+        } else {
+            Acquisition::Succession(rules.law)
+        };
+        for (_, mut title) in &mut query_titles {
+            let predecessors = title
+                .history
+                .last()
+                .is_some_and(|tenure| tenure.holder == event.predecessor());
+            if title.rank != TitleRank::Kingdom || !predecessors {
+                continue;
+            }
+            // a monarch who lost a succession crisis is still holding the crown
+            if title.holder.is_some() {
+                title.vacate(date, "was deposed");
+            }
+            title.grant(event.heir(), &name.first, date, acquired);
+        }
+    }
+}
+
+/// Keep the reign that dates are counted by in step with whoever holds the kingdom
+fn track_reign(
+    mut commands: Commands,
+    query_titles: Query<&LandedTitle, Changed<LandedTitle>>,
+    query_genders: Query<&Gender>,
+) {
+    for title in &query_titles {
+        if title.rank != TitleRank::Kingdom {
+            continue;
+        }
+        let Some(tenure) = title.current() else {
+            commands.remove_resource::<Reign>();
+            continue;
+        };
+        let gender = query_genders
+            .get(tenure.holder)
+            .copied()
+            .unwrap_or(Gender::NonBinary);
+        commands.insert_resource(Reign {
+            monarch: format!(
+                "{} {}",
+                title.rank.styled(gender),
+                title.regnal_name(tenure)
+            ),
+            since: tenure.from,
+        });
+    }
+}
+
+/// One line of the list of monarchs, e.g.
+/// "King Jack II of House Allan, 3 Messidor, Year 0 to 9 Termidor, Year 12 (succeeded by ..., died of ...)"
+fn describe_reign(
+    title: &LandedTitle,
+    tenure: &Tenure,
+    gender: Gender,
+    dynasty: Option<&Dynasty>,
+) -> String {
+    let house = dynasty.map_or_else(String::new, |dynasty| format!(" of {dynasty}"));
+    let until = tenure
+        .until
+        .map_or_else(|| "the present day".to_string(), |until| until.to_string());
+    let lost = tenure
+        .lost
+        .as_ref()
+        .map_or_else(String::new, |lost| format!(", {lost}"));
+    format!(
+        "{} {}{}, {} to {} ({}{})",
+        title.rank.styled(gender),
+        title.regnal_name(tenure),
+        house,
+        tenure.from,
+        until,
+        tenure.acquired,
+        lost
+    )
+}
+
+/// Write out every monarch the kingdom has had at the start of each year
+fn export_monarchs(
+    query_calendar: Query<&Calendar>,
+    query_titles: Query<&LandedTitle>,
+    query_people: Query<(&Gender, Option<&InDynasty>)>,
+    query_dynasties: Query<&Dynasty>,
+) {
+    if query_calendar.single().year_day() != 1 {
+        return;
+    }
+
+    let mut list = String::new();
+    for title in query_titles
+        .iter()
+        .filter(|title| title.rank == TitleRank::Kingdom)
+    {
+        let _ = writeln!(list, "Monarchs of {}", title.name);
+        for tenure in &title.history {
+            let (gender, member) = query_people
+                .get(tenure.holder)
+                .map_or((Gender::NonBinary, None), |(gender, member)| {
+                    (*gender, member)
+                });
+            let dynasty = member.and_then(|member| query_dynasties.get(member.dynasty).ok());
+            list.push_str(&describe_reign(title, tenure, gender, dynasty));
+            list.push('\n');
+        }
+    }
+    match std::fs::write(EXPORT_PATH, list) {
+        Ok(()) => debug!("Exported the list of monarchs to {}", EXPORT_PATH),
+        Err(error) => warn!("Couldn't export the list of monarchs: {}", error),
+    }
+}
+
+pub struct TitlePlugin;
+
+impl Plugin for TitlePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(PostStartup, found_realm).add_systems(
+            Update,
+            (inherit_titles, track_reign, export_monarchs)
+                .chain()
+                .run_if(in_state(RunState::Running)),
+        );
+    }
+}
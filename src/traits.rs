@@ -0,0 +1,207 @@
+use bevy::{prelude::*, utils::HashMap};
+use rand::prelude::*;
+use statrs::distribution::Normal;
+
+use crate::people::Name;
+
+/// How much a child's blended attributes can stray from the average of their parents
+const BLEND_STD: f64 = 0.08;
+
+/// Features passed down with a pair of alleles, one from each parent
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Gene {
+    HookedNose,
+    RedHair,
+    GreenEyes,
+    Bleeding,
+    Madness,
+}
+
+impl Gene {
+    pub const ALL: [Gene; 5] = [
+        Gene::HookedNose,
+        Gene::RedHair,
+        Gene::GreenEyes,
+        Gene::Bleeding,
+        Gene::Madness,
+    ];
+
+    /// Dominant genes show up with one copy, recessive ones need two
+    pub fn dominant(self) -> bool {
+        matches!(self, Gene::HookedNose)
+    }
+
+    /// Chance one of a founder's alleles carries the gene
+    fn founder_frequency(self) -> f64 {
+        match self {
+            Gene::HookedNose => 0.15,
+            Gene::RedHair => 0.3,
+            Gene::GreenEyes => 0.35,
+            Gene::Bleeding => 0.1,
+            Gene::Madness => 0.08,
+        }
+    }
+
+    pub fn is_condition(self) -> bool {
+        matches!(self, Gene::Bleeding | Gene::Madness)
+    }
+
+    /// How the feature reads in a story, e.g. "the Allan nose"
+    pub fn description(self) -> &'static str {
+        match self {
+            Gene::HookedNose => "nose",
+            Gene::RedHair => "red hair",
+            Gene::GreenEyes => "green eyes",
+            Gene::Bleeding => "bleeding sickness",
+            Gene::Madness => "madness",
+        }
+    }
+}
+
+/// Which of the two copies of a gene carry it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Alleles {
+    pub maternal: bool,
+    pub paternal: bool,
+}
+
+impl Alleles {
+    /// Pass one of the two copies on to a child
+    fn pass_on(self, rng: &mut ThreadRng) -> bool {
+        if rng.gen() {
+            self.maternal
+        } else {
+            self.paternal
+        }
+    }
+}
+
+/// Features that blend, the child lands somewhere around the middle of their parents
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Attribute {
+    Height,
+    Strength,
+    Beauty,
+    Wit,
+    Temper,
+    Music,
+    Swordplay,
+    Craft,
+}
+
+impl Attribute {
+    pub const ALL: [Attribute; 8] = [
+        Attribute::Height,
+        Attribute::Strength,
+        Attribute::Beauty,
+        Attribute::Wit,
+        Attribute::Temper,
+        Attribute::Music,
+        Attribute::Swordplay,
+        Attribute::Craft,
+    ];
+}
+
+/// Heritable physical features, temperament, talents and conditions
+#[derive(Component, Debug, Clone)]
+pub struct Traits {
+    pub genes: HashMap<Gene, Alleles>,
+    /// Blended attributes, from 0 to 1
+    pub attributes: HashMap<Attribute, f64>,
+}
+
+impl Traits {
+    /// Traits for someone with no known parents
+    pub fn random(rng: &mut ThreadRng) -> Self {
+        let genes = Gene::ALL
+            .into_iter()
+            .map(|gene| {
+                let frequency = gene.founder_frequency();
+                let alleles = Alleles {
+                    maternal: rng.gen_bool(frequency),
+                    paternal: rng.gen_bool(frequency),
+                };
+                (gene, alleles)
+            })
+            .collect();
+
+        let attributes = Attribute::ALL
+            .into_iter()
+            .map(|attribute| (attribute, rng.gen_range(0.0..1.0)))
+            .collect();
+
+        Traits { genes, attributes }
+    }
+
+    /// Combine a mother's and father's traits into a child's
+    pub fn inherit(mother: &Traits, father: &Traits, rng: &mut ThreadRng) -> Self {
+        let genes = Gene::ALL
+            .into_iter()
+            .map(|gene| {
+                let alleles = Alleles {
+                    maternal: mother.alleles(gene).pass_on(rng),
+                    paternal: father.alleles(gene).pass_on(rng),
+                };
+                (gene, alleles)
+            })
+            .collect();
+
+        let noise = Normal::new(0.0, BLEND_STD).unwrap();
+        let attributes = Attribute::ALL
+            .into_iter()
+            .map(|attribute| {
+                let mean = f64::midpoint(mother.attribute(attribute), father.attribute(attribute));
+                (attribute, (mean + noise.sample(rng)).clamp(0.0, 1.0))
+            })
+            .collect();
+
+        Traits { genes, attributes }
+    }
+
+    pub fn alleles(&self, gene: Gene) -> Alleles {
+        self.genes.get(&gene).copied().unwrap_or_default()
+    }
+
+    pub fn attribute(&self, attribute: Attribute) -> f64 {
+        self.attributes.get(&attribute).copied().unwrap_or(0.5)
+    }
+
+    /// Whether the gene actually shows, rather than just being carried
+    pub fn expresses(&self, gene: Gene) -> bool {
+        let alleles = self.alleles(gene);
+        if gene.dominant() {
+            alleles.maternal || alleles.paternal
+        } else {
+            alleles.maternal && alleles.paternal
+        }
+    }
+}
+
+/// Tell the story of what a newborn got from their parents, e.g. "the Allan nose"
+pub fn describe_inheritance(child: &Name, traits: &Traits, parents: [(&Name, &Traits); 2]) {
+    for gene in Gene::ALL {
+        if !traits.expresses(gene) {
+            continue;
+        }
+
+        if gene.is_condition() {
+            info!(
+                "{} {} was born with the {}",
+                child.first,
+                child.last,
+                gene.description()
+            );
+        } else if let Some((parent, _)) = parents
+            .iter()
+            .find(|(_, parent_traits)| parent_traits.expresses(gene))
+        {
+            info!(
+                "{} {} has the {} {}",
+                child.first,
+                child.last,
+                parent.last,
+                gene.description()
+            );
+        }
+    }
+}